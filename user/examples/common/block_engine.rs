@@ -0,0 +1,143 @@
+//! Stand-in for the Jito block engine JSON-RPC API, forwarding every bundle
+//! transaction to a Solana RPC node.
+//!
+//! Bundles are not atomic here: their transactions are sent one by one.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use mantis_sdk::jito::BUNDLES_PATH;
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+
+/// Answers `sendBundle`, `getInflightBundleStatuses` and
+/// `getBundleStatuses` as `mantis_sdk::jito` uses them.
+pub struct BlockEngine {
+    rpc_client: RpcClient,
+    /// Transaction signatures of every bundle, by bundle id.
+    bundles: HashMap<String, Vec<Signature>>,
+}
+
+impl BlockEngine {
+    /// Block engine forwarding bundles to the Solana RPC node at `rpc_url`.
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            rpc_client: RpcClient::new(rpc_url),
+            bundles: HashMap::new(),
+        }
+    }
+
+    /// Answer the JSON-RPC `request` sent to `path`, returning the response
+    /// body.
+    pub fn handle(&mut self, path: &str, request: Value) -> Result<String> {
+        if path != BUNDLES_PATH {
+            bail!("Unknown path {}", path);
+        }
+
+        let params = &request["params"];
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "sendBundle" => self.send_bundle(&params[0]),
+            "getInflightBundleStatuses" => self.statuses(&params[0], Self::inflight_status),
+            "getBundleStatuses" => self.statuses(&params[0], Self::bundle_status),
+            method => Err(anyhow!("Unknown method {}", method)),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32000, "message": e.to_string() },
+            }),
+        };
+        Ok(response.to_string())
+    }
+
+    /// Send every transaction of the bundle, named after its first signature.
+    fn send_bundle(&mut self, transactions: &Value) -> Result<Value> {
+        let mut signatures = Vec::new();
+        for transaction in transactions
+            .as_array()
+            .ok_or_else(|| anyhow!("No transactions"))?
+        {
+            let bytes = BASE64.decode(transaction.as_str().unwrap_or_default())?;
+            let transaction: Transaction = bincode::deserialize(&bytes)?;
+            signatures.push(self.rpc_client.send_transaction_with_config(
+                &transaction,
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..Default::default()
+                },
+            )?);
+        }
+
+        let bundle_id = signatures
+            .first()
+            .ok_or_else(|| anyhow!("Empty bundle"))?
+            .to_string();
+        println!(
+            "Bundle {} with {} transactions",
+            bundle_id,
+            signatures.len()
+        );
+        self.bundles.insert(bundle_id.clone(), signatures);
+        Ok(json!(bundle_id))
+    }
+
+    fn statuses(
+        &self,
+        bundle_ids: &Value,
+        status: fn(&Self, &str, &[Signature]) -> Result<Value>,
+    ) -> Result<Value> {
+        let mut value = Vec::new();
+        for bundle_id in bundle_ids
+            .as_array()
+            .ok_or_else(|| anyhow!("No bundle ids"))?
+        {
+            let bundle_id = bundle_id.as_str().unwrap_or_default();
+            value.push(match self.bundles.get(bundle_id) {
+                Some(signatures) => status(self, bundle_id, signatures)?,
+                None => Value::Null,
+            });
+        }
+        Ok(json!({ "context": { "slot": self.rpc_client.get_slot()? }, "value": value }))
+    }
+
+    fn inflight_status(&self, bundle_id: &str, signatures: &[Signature]) -> Result<Value> {
+        let statuses = self.rpc_client.get_signature_statuses(signatures)?.value;
+        let (status, landed_slot) = if statuses.iter().flatten().any(|s| s.err.is_some()) {
+            ("Failed", None)
+        } else if statuses.iter().all(Option::is_some) {
+            ("Landed", statuses.iter().flatten().map(|s| s.slot).max())
+        } else {
+            ("Pending", None)
+        };
+        Ok(json!({ "bundle_id": bundle_id, "status": status, "landed_slot": landed_slot }))
+    }
+
+    fn bundle_status(&self, bundle_id: &str, signatures: &[Signature]) -> Result<Value> {
+        let statuses = self.rpc_client.get_signature_statuses(signatures)?.value;
+        let Some(statuses) = statuses.into_iter().collect::<Option<Vec<_>>>() else {
+            return Ok(Value::Null);
+        };
+        // The least confirmed transaction decides for the whole bundle.
+        let confirmation_status = statuses
+            .iter()
+            .map(|status| status.confirmation_status())
+            .min_by_key(|status| status.clone() as u8);
+        Ok(json!({
+            "bundle_id": bundle_id,
+            "transactions": signatures.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "slot": statuses.iter().map(|status| status.slot).max(),
+            "confirmation_status": confirmation_status,
+            "err": match statuses.iter().find_map(|status| status.err.clone()) {
+                Some(err) => json!({ "Err": err.to_string() }),
+                None => json!({ "Ok": null }),
+            },
+        }))
+    }
+}
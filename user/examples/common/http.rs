@@ -0,0 +1,64 @@
+//! HTTP server of the stand-ins: one request with a JSON body per
+//! connection, answered with `Connection: close`.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// Answer every request on `listener` with the body `handle` returns for its
+/// path and JSON body, or with `400 Bad Request` and the error.
+pub fn serve(
+    listener: TcpListener,
+    mut handle: impl FnMut(&str, Value) -> Result<String>,
+) -> Result<()> {
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let (status, body) =
+            match read_request(&mut stream).and_then(|(path, body)| handle(&path, body)) {
+                Ok(body) => ("200 OK", body),
+                Err(e) => ("400 Bad Request", e.to_string()),
+            };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+    }
+    Ok(())
+}
+
+/// Path and JSON body of the request on `stream`, `null` without a body.
+fn read_request(stream: &mut TcpStream) -> Result<(String, Value)> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .ok_or_else(|| anyhow!("Malformed request"))?
+        .to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+    if content_length == 0 {
+        return Ok((path, Value::Null));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok((path, serde_json::from_slice(&body)?))
+}
@@ -0,0 +1,49 @@
+//! Stand-in for a remote signing service, answering both protocols of
+//! `mantis_sdk::signer` with keys held in process.
+
+use anyhow::{anyhow, bail, Result};
+use ethers::signers::{LocalWallet, Signer as _};
+use ethers::types::H256;
+use ethers::utils::{hex, keccak256};
+use serde_json::{json, Value};
+use solana_sdk::bs58;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer as _;
+
+/// Keys the stand-in signs with, each optional.
+pub struct Keys {
+    pub solana: Option<Keypair>,
+    pub ethereum: Option<LocalWallet>,
+}
+
+impl Keys {
+    /// Sign the request `body` sent to `path`, returning the response body.
+    pub fn sign(&self, path: &str, body: Value) -> Result<String> {
+        if path == "/solana" {
+            let keypair = self
+                .solana
+                .as_ref()
+                .ok_or_else(|| anyhow!("No Solana key"))?;
+            if body["pubkey"].as_str() != Some(keypair.pubkey().to_string().as_str()) {
+                bail!("Unknown Solana key {}", body["pubkey"]);
+            }
+            let message = bs58::decode(body["message"].as_str().unwrap_or_default()).into_vec()?;
+            let signature = keypair.sign_message(&message);
+            Ok(json!({ "signature": signature.to_string() }).to_string())
+        } else if let Some(address) = path.strip_prefix("/api/v1/eth1/sign/") {
+            let wallet = self
+                .ethereum
+                .as_ref()
+                .ok_or_else(|| anyhow!("No Ethereum key"))?;
+            if !address.eq_ignore_ascii_case(&format!("{:?}", wallet.address())) {
+                bail!("Unknown Ethereum key {}", address);
+            }
+            let data = body["data"].as_str().unwrap_or_default();
+            let data = hex::decode(data.trim_start_matches("0x"))?;
+            let signature = wallet.sign_hash(H256::from(keccak256(&data)), false);
+            Ok(format!("0x{}", signature))
+        } else {
+            bail!("Unknown path {}", path)
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Local stand-in for the Jito block engine JSON-RPC API, forwarding every
+//! bundle transaction to a Solana RPC node such as `solana-test-validator`.
+//!
+//! It answers `sendBundle`, `getInflightBundleStatuses` and
+//! `getBundleStatuses` as `mantis_sdk::jito` uses them, so `jito_http` can
+//! be tried on localnet:
+//!
+//! ```bash
+//! cargo run --example stand_in_block_engine -- 127.0.0.1:9100 http://127.0.0.1:8899
+//! JITO_BLOCK_ENGINE_URL=http://127.0.0.1:9100 cargo run -- --network localnet \
+//!     solana ... --tx-send-method jito_http
+//! ```
+
+#[path = "common/block_engine.rs"]
+mod block_engine;
+#[path = "common/http.rs"]
+mod http;
+
+use std::env;
+use std::net::TcpListener;
+
+use anyhow::Result;
+use mantis_sdk::jito::BUNDLES_PATH;
+
+use crate::block_engine::BlockEngine;
+
+fn main() -> Result<()> {
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:9100".to_string());
+    let rpc_url = env::args()
+        .nth(2)
+        .unwrap_or_else(|| "http://127.0.0.1:8899".to_string());
+    let mut engine = BlockEngine::new(rpc_url.clone());
+
+    let listener = TcpListener::bind(&address)?;
+    println!(
+        "Listening on http://{}{}, forwarding to {}",
+        address, BUNDLES_PATH, rpc_url
+    );
+    http::serve(listener, |path, request| engine.handle(path, request))
+}
@@ -0,0 +1,58 @@
+//! Local stand-in for a remote signing service, holding the keys of
+//! `SOLANA_KEYPAIR` and `ETHEREUM_PKEY` in process.
+//!
+//! It answers both protocols of `mantis_sdk::signer`, so remote signing can
+//! be tried without an HSM:
+//!
+//! ```bash
+//! cargo run --example stand_in_signer -- 127.0.0.1:9000
+//! ```
+//!
+//! and in `mantis.toml`:
+//!
+//! ```toml
+//! solana_key = { remote = { url = "http://127.0.0.1:9000/solana", key = "<pubkey>" } }
+//! ethereum_key = { remote = { url = "http://127.0.0.1:9000", key = "<address>" } }
+//! ```
+
+#[path = "common/http.rs"]
+mod http;
+#[path = "common/signer.rs"]
+mod signer;
+
+use std::env;
+use std::net::TcpListener;
+
+use anyhow::Result;
+use ethers::signers::Signer as _;
+use mantis_sdk::keys::{ethereum_wallet_from_hex, solana_keypair_from_base58};
+use solana_sdk::signer::Signer as _;
+
+use crate::signer::Keys;
+
+fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:9000".to_string());
+    let keys = Keys {
+        solana: env::var("SOLANA_KEYPAIR")
+            .ok()
+            .map(|key| solana_keypair_from_base58(&key))
+            .transpose()?,
+        ethereum: env::var("ETHEREUM_PKEY")
+            .ok()
+            .map(|key| ethereum_wallet_from_hex(&key))
+            .transpose()?,
+    };
+    if let Some(keypair) = &keys.solana {
+        println!("Solana key:   {}", keypair.pubkey());
+    }
+    if let Some(wallet) = &keys.ethereum {
+        println!("Ethereum key: {:?}", wallet.address());
+    }
+
+    let listener = TcpListener::bind(&address)?;
+    println!("Listening on http://{}", address);
+    http::serve(listener, |path, body| keys.sign(path, body))
+}
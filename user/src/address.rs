@@ -0,0 +1,205 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{anyhow, bail, Result};
+use ethers::types::H160;
+use ethers::utils::to_checksum;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::intent::Domain;
+
+/// An account or token address on one of the supported domains.
+///
+/// Ethereum addresses are `0x`-prefixed hex and, when written in mixed case,
+/// must carry a valid EIP-55 checksum. Solana addresses are base58 encoded
+/// 32 byte public keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DomainAddress {
+    Solana(Pubkey),
+    Ethereum(H160),
+}
+
+impl DomainAddress {
+    /// Parse `address` as an address on `domain`.
+    pub fn parse(domain: Domain, address: &str) -> Result<Self> {
+        match domain {
+            Domain::Solana => parse_solana_address(address).map(Self::Solana),
+            Domain::Ethereum => parse_ethereum_address(address).map(Self::Ethereum),
+        }
+    }
+
+    pub fn domain(&self) -> Domain {
+        match self {
+            Self::Solana(_) => Domain::Solana,
+            Self::Ethereum(_) => Domain::Ethereum,
+        }
+    }
+
+    pub fn as_solana(&self) -> Result<Pubkey> {
+        match self {
+            Self::Solana(pubkey) => Ok(*pubkey),
+            Self::Ethereum(address) => {
+                bail!(
+                    "Expected a Solana address, got {}",
+                    to_checksum(address, None)
+                )
+            }
+        }
+    }
+
+    pub fn as_ethereum(&self) -> Result<H160> {
+        match self {
+            Self::Ethereum(address) => Ok(*address),
+            Self::Solana(pubkey) => bail!("Expected an Ethereum address, got {}", pubkey),
+        }
+    }
+}
+
+/// Infer the domain from the format: `0x`-prefixed addresses are Ethereum,
+/// everything else must be a Solana public key.
+impl FromStr for DomainAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(address: &str) -> Result<Self> {
+        if address.starts_with("0x") || address.starts_with("0X") {
+            Self::parse(Domain::Ethereum, address)
+        } else {
+            Self::parse(Domain::Solana, address)
+        }
+    }
+}
+
+impl fmt::Display for DomainAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Solana(pubkey) => write!(f, "{}", pubkey),
+            Self::Ethereum(address) => write!(f, "{}", to_checksum(address, None)),
+        }
+    }
+}
+
+impl Serialize for DomainAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DomainAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let address = String::deserialize(deserializer)?;
+        address.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn parse_solana_address(address: &str) -> Result<Pubkey> {
+    if address.starts_with("0x") || address.starts_with("0X") {
+        bail!(
+            "{} is an Ethereum address, expected a base58 Solana address",
+            address
+        );
+    }
+    Pubkey::from_str(address).map_err(|e| anyhow!("Invalid Solana address {}: {}", address, e))
+}
+
+fn parse_ethereum_address(address: &str) -> Result<H160> {
+    let hex = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .ok_or_else(|| anyhow!("{} is not a 0x-prefixed Ethereum address", address))?;
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!(
+            "Invalid Ethereum address {}: expected 40 hex characters",
+            address
+        );
+    }
+
+    let parsed =
+        H160::from_str(hex).map_err(|e| anyhow!("Invalid Ethereum address {}: {}", address, e))?;
+
+    // All-lowercase and all-uppercase addresses carry no checksum.
+    let is_mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if is_mixed_case {
+        let checksummed = to_checksum(&parsed, None);
+        if checksummed[2..] != *hex {
+            bail!(
+                "Invalid EIP-55 checksum for Ethereum address {}, did you mean {}?",
+                address,
+                checksummed
+            );
+        }
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mixed-case examples of EIP-55.
+    const CHECKSUMMED: [&str; 4] = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn checksummed_ethereum_addresses() {
+        for address in CHECKSUMMED {
+            let parsed = DomainAddress::parse(Domain::Ethereum, address).unwrap();
+            assert_eq!(parsed.to_string(), address);
+        }
+    }
+
+    #[test]
+    fn single_case_ethereum_addresses_carry_no_checksum() {
+        for address in CHECKSUMMED {
+            let lower = address.to_lowercase();
+            let upper = format!("0x{}", address[2..].to_uppercase());
+            for unchecked in [lower, upper] {
+                let parsed: DomainAddress = unchecked.parse().unwrap();
+                assert_eq!(parsed.to_string(), address);
+            }
+        }
+    }
+
+    #[test]
+    fn bad_checksum_is_rejected() {
+        let err = DomainAddress::parse(
+            Domain::Ethereum,
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains(CHECKSUMMED[0]), "{}", err);
+    }
+
+    #[test]
+    fn malformed_ethereum_addresses_are_rejected() {
+        for address in [
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAedgg",
+            "0xzaaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+        ] {
+            assert!(
+                DomainAddress::parse(Domain::Ethereum, address).is_err(),
+                "{}",
+                address
+            );
+        }
+    }
+
+    #[test]
+    fn addresses_of_the_other_domain_are_rejected() {
+        let solana = "So11111111111111111111111111111111111111112";
+        assert!(DomainAddress::parse(Domain::Ethereum, solana).is_err());
+        assert!(DomainAddress::parse(Domain::Solana, CHECKSUMMED[0]).is_err());
+
+        let parsed: DomainAddress = solana.parse().unwrap();
+        assert_eq!(parsed.domain(), Domain::Solana);
+        assert!(parsed.as_ethereum().is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use ethers::types::U256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Token amount in base units, optionally tagged with the token's decimals.
+///
+/// Raw amounts (`"1500000"`) are integers in base units and can be as large
+/// as a `U256`. Human amounts (`"1.5"`) are scaled by the token's decimals
+/// when parsed. Solana escrows need the raw value to fit in a `u64`, which
+/// [`TokenAmount::to_u64`] checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokenAmount {
+    raw: U256,
+    decimals: Option<u8>,
+}
+
+impl TokenAmount {
+    pub fn from_raw(raw: impl Into<U256>) -> Self {
+        Self {
+            raw: raw.into(),
+            decimals: None,
+        }
+    }
+
+    pub fn with_decimals(mut self, decimals: u8) -> Self {
+        self.decimals = Some(decimals);
+        self
+    }
+
+    /// Parse an integer amount in base units.
+    pub fn parse_raw(amount: &str) -> Result<Self> {
+        let amount = amount.trim();
+        if amount.is_empty() || !amount.chars().all(|c| c.is_ascii_digit()) {
+            bail!(
+                "Invalid amount {}: expected an integer amount in base units",
+                amount
+            );
+        }
+        let raw = U256::from_dec_str(amount)
+            .map_err(|e| anyhow!("Invalid amount {}: {:?}", amount, e))?;
+        Ok(Self::from_raw(raw))
+    }
+
+    /// Parse a human amount such as `1.5` for a token with `decimals` decimals.
+    pub fn parse_ui(amount: &str, decimals: u8) -> Result<Self> {
+        let amount = amount.trim();
+        let (integer, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if (integer.is_empty() && fraction.is_empty())
+            || !is_digits(integer)
+            || !is_digits(fraction)
+        {
+            bail!("Invalid amount {}: expected a decimal number", amount);
+        }
+        if fraction.len() > decimals as usize {
+            bail!(
+                "Invalid amount {}: the token only has {} decimals",
+                amount,
+                decimals
+            );
+        }
+
+        // 10^77 is the largest power of ten that fits in a U256.
+        if decimals > 77 {
+            bail!("Unsupported token decimals {}", decimals);
+        }
+
+        let parse = |part: &str| {
+            if part.is_empty() {
+                Ok(U256::zero())
+            } else {
+                U256::from_dec_str(part).map_err(|_| anyhow!("Amount {} is too large", amount))
+            }
+        };
+        let fraction_len = fraction.len();
+        let integer = parse(integer)?;
+        let fraction = parse(fraction)? * U256::exp10(decimals as usize - fraction_len);
+        let raw = integer
+            .checked_mul(U256::exp10(decimals as usize))
+            .and_then(|scaled| scaled.checked_add(fraction))
+            .ok_or_else(|| anyhow!("Amount {} is too large", amount))?;
+
+        Ok(Self::from_raw(raw).with_decimals(decimals))
+    }
+
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> Option<u8> {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Raw value as a `u64`, as required by Solana token amounts.
+    pub fn to_u64(&self) -> Result<u64> {
+        if self.raw > U256::from(u64::MAX) {
+            bail!("Amount {} does not fit in a u64", self.raw);
+        }
+        Ok(self.raw.as_u64())
+    }
+
+    /// Human readable amount, if the token's decimals are known.
+    pub fn to_ui_string(&self) -> Option<String> {
+        let decimals = self.decimals.filter(|decimals| *decimals <= 77)? as usize;
+        let scale = U256::exp10(decimals);
+        let integer = self.raw / scale;
+        let fraction = format!(
+            "{:0>width$}",
+            (self.raw % scale).to_string(),
+            width = decimals
+        );
+        let fraction = fraction.trim_end_matches('0');
+
+        Some(if fraction.is_empty() {
+            integer.to_string()
+        } else {
+            format!("{}.{}", integer, fraction)
+        })
+    }
+}
+
+impl From<u64> for TokenAmount {
+    fn from(raw: u64) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+impl From<U256> for TokenAmount {
+    fn from(raw: U256) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+impl FromStr for TokenAmount {
+    type Err = anyhow::Error;
+
+    fn from_str(amount: &str) -> Result<Self> {
+        Self::parse_raw(amount)
+    }
+}
+
+/// Formats the raw amount in base units.
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+/// Serialized form: raw amounts are decimal strings so values beyond `u64`
+/// survive JSON, with the decimals alongside when they are known.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TokenAmountRepr {
+    Number(u64),
+    Raw(String),
+    WithDecimals { raw: String, decimals: u8 },
+}
+
+impl Serialize for TokenAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let raw = self.raw.to_string();
+        match self.decimals {
+            Some(decimals) => TokenAmountRepr::WithDecimals { raw, decimals },
+            None => TokenAmountRepr::Raw(raw),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TokenAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match TokenAmountRepr::deserialize(deserializer)? {
+            TokenAmountRepr::Number(raw) => Ok(Self::from_raw(raw)),
+            TokenAmountRepr::Raw(raw) => Self::parse_raw(&raw).map_err(serde::de::Error::custom),
+            TokenAmountRepr::WithDecimals { raw, decimals } => Self::parse_raw(&raw)
+                .map(|amount| amount.with_decimals(decimals))
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ui(amount: &str, decimals: u8) -> U256 {
+        TokenAmount::parse_ui(amount, decimals).unwrap().raw()
+    }
+
+    #[test]
+    fn parse_ui_scales_by_decimals() {
+        assert_eq!(ui("1.5", 6), U256::from(1_500_000));
+        assert_eq!(ui("1", 9), U256::from(1_000_000_000));
+        assert_eq!(ui(".5", 2), U256::from(50));
+        assert_eq!(ui("2.", 2), U256::from(200));
+        assert_eq!(ui(" 0.000001 ", 6), U256::one());
+        assert_eq!(ui("42", 0), U256::from(42));
+        assert_eq!(
+            ui("1.000000000000000001", 18),
+            U256::exp10(18) + U256::one()
+        );
+    }
+
+    #[test]
+    fn parse_ui_keeps_decimals_and_round_trips() {
+        let amount = TokenAmount::parse_ui("1.25", 6).unwrap();
+        assert_eq!(amount.decimals(), Some(6));
+        assert_eq!(amount.to_ui_string().as_deref(), Some("1.25"));
+        assert_eq!(amount.to_string(), "1250000");
+    }
+
+    #[test]
+    fn parse_ui_rejects_malformed_amounts() {
+        for amount in ["", ".", "-1", "1,5", "1.2.3", "1e6", "abc", "0x10"] {
+            assert!(TokenAmount::parse_ui(amount, 6).is_err(), "{:?}", amount);
+        }
+    }
+
+    #[test]
+    fn parse_ui_rejects_more_fraction_digits_than_decimals() {
+        let err = TokenAmount::parse_ui("1.1234567", 6).unwrap_err();
+        assert!(err.to_string().contains("only has 6 decimals"), "{}", err);
+        assert!(TokenAmount::parse_ui("0.1", 0).is_err());
+    }
+
+    #[test]
+    fn parse_ui_rejects_overflow() {
+        assert!(TokenAmount::parse_ui("1", 78).is_err());
+        let max = U256::MAX.to_string();
+        assert!(TokenAmount::parse_ui(&max, 1).is_err());
+        assert_eq!(ui(&max, 0), U256::MAX);
+    }
+}
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Signature;
+
+pub async fn send_signature_to_auctioneer(
+    auctioneer_url: &str,
+    signature: Signature,
+) -> Result<()> {
+    let request = SolanaTxHashRequest {
+        tx_hash: signature.to_string(),
+    };
+
+    let http_response = reqwest::Client::new()
+        .post(format!("{auctioneer_url}/solana_tx_hash"))
+        .json(&request)
+        .send()
+        .await?;
+
+    println!(
+        "Status of solana_tx_hash submission: {}",
+        http_response.status()
+    );
+
+    let _response: SolanaTxHashResponse = http_response.json().await?;
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SolanaTxHashRequest {
+    pub tx_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SolanaTxHashResponse {
+    pub intent_id: String,
+}
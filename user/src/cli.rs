@@ -1,16 +1,163 @@
-use crate::Pubkey;
-use clap::{Arg, ArgMatches, Command};
-use std::str::FromStr;
-use crate::solana::TxSendMethod;
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use mantis_sdk::address::DomainAddress;
+use mantis_sdk::amount::TokenAmount;
+use mantis_sdk::config::Profile;
+use mantis_sdk::intent::{Domain, IntentRequest};
+use mantis_sdk::network::NetworkName;
+use mantis_sdk::solana::TxSendMethod;
+use mantis_sdk::MantisClient;
+use std::path::PathBuf;
 
 pub fn parse_cli() -> ArgMatches {
     Command::new("Mantis SDK Intent CLI")
         .version("1.0")
         .about("Handles Solana and Ethereum escrow intents. Single Domain & Cross Domain")
+        .arg(
+            Arg::new("network")
+                .long("network")
+                .global(true)
+                .default_value(&NetworkName::Mainnet)
+                .value_parser(clap::value_parser!(NetworkName))
+                .help("Network profile (mainnet/devnet/sepolia/localnet/custom)"),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .global(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Config file, defaults to $MANTIS_CONFIG or ./mantis.toml"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .global(true)
+                .help("Profile of the config file to use, defaults to `default`"),
+        )
+        .arg(
+            Arg::new("keypair")
+                .long("keypair")
+                .global(true)
+                .help("Solana keypair file (JSON byte array), or `prompt` to type a seed phrase"),
+        )
+        .arg(
+            Arg::new("derivation_path")
+                .long("derivation-path")
+                .global(true)
+                .requires("keypair")
+                .help("Derivation path for a seed phrase, defaults to m/44'/501'/0'/0'"),
+        )
+        .arg(
+            Arg::new("fee_payer")
+                .long("fee-payer")
+                .global(true)
+                .help("Solana keypair file paying fees, Jito tips and rent instead of --keypair"),
+        )
+        .arg(
+            Arg::new("priority_fee")
+                .long("priority-fee")
+                .global(true)
+                .value_parser(clap::value_parser!(u64))
+                .help("Fixed Solana priority fee in micro-lamports per compute unit"),
+        )
+        .arg(
+            Arg::new("min_priority_fee")
+                .long("min-priority-fee")
+                .global(true)
+                .conflicts_with("priority_fee")
+                .value_parser(clap::value_parser!(u64))
+                .help("Lowest priority fee derived from recent fees, in micro-lamports"),
+        )
+        .arg(
+            Arg::new("max_priority_fee")
+                .long("max-priority-fee")
+                .global(true)
+                .conflicts_with("priority_fee")
+                .value_parser(clap::value_parser!(u64))
+                .help("Highest priority fee derived from recent fees, in micro-lamports"),
+        )
+        .arg(
+            Arg::new("compute_unit_limit")
+                .long("compute-unit-limit")
+                .global(true)
+                .value_parser(clap::value_parser!(u32))
+                .help("Solana compute unit limit, sized by simulation by default"),
+        )
+        .arg(
+            Arg::new("jito_tip_strategy")
+                .long("jito-tip-strategy")
+                .global(true)
+                .help("Jito tip: fixed, percentile:<25|50|75|95|99> or escalate:<lamports>"),
+        )
+        .arg(
+            Arg::new("max_jito_tip")
+                .long("max-jito-tip")
+                .global(true)
+                .value_parser(clap::value_parser!(u64))
+                .help("Highest Jito tip in lamports a tip strategy may pay"),
+        )
+        .arg(
+            Arg::new("jito_regions")
+                .long("jito-regions")
+                .global(true)
+                .help("Jito regions or block engine URLs to race bundles through, or `all`"),
+        )
+        .arg(
+            Arg::new("jito_race")
+                .long("jito-race")
+                .global(true)
+                .value_parser(clap::value_parser!(usize))
+                .help("Race each bundle through this many regions with the lowest latency"),
+        )
+        .arg(
+            Arg::new("jito_auth_keypair")
+                .long("jito-auth-keypair")
+                .global(true)
+                .help("Whitelisted Jito keypair file for `jito`, defaults to the fee payer"),
+        )
+        .arg(
+            Arg::new("keystore")
+                .long("keystore")
+                .global(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Encrypted V3 JSON keystore holding the Ethereum key"),
+        )
+        .arg(
+            Arg::new("password_file")
+                .long("password-file")
+                .global(true)
+                .requires("keystore")
+                .conflicts_with("password_fd")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Read the keystore passphrase from this file instead of prompting"),
+        )
+        .arg(
+            Arg::new("password_fd")
+                .long("password-fd")
+                .global(true)
+                .requires("keystore")
+                .value_parser(clap::value_parser!(u32))
+                .help("Read the keystore passphrase from this file descriptor"),
+        )
+        .arg(
+            Arg::new("mnemonic")
+                .long("mnemonic")
+                .global(true)
+                .conflicts_with_all(["keypair", "keystore"])
+                .help("Seed phrase file, or `prompt`, deriving both the Solana and Ethereum keys"),
+        )
+        .arg(
+            Arg::new("account")
+                .long("account")
+                .global(true)
+                .requires("mnemonic")
+                .value_parser(clap::value_parser!(u32))
+                .help("Account index derived from --mnemonic, defaults to 0"),
+        )
         .subcommand(
             Command::new("solana")
                 .about("Solana -> Solana single domain intent")
-                .args(common_args()), // Use common_args for Solana
+                .args(with_optional_timeout(common_args())), // Use common_args for Solana
         )
         .subcommand(
             Command::new("solana-ethereum")
@@ -20,30 +167,320 @@ pub fn parse_cli() -> ArgMatches {
         .subcommand(
             Command::new("ethereum")
                 .about("Ethereum -> Ethereum single domain intent")
-                .args(common_args_ethereum()), // Use common_args_ethereum for Ethereum
+                .args(with_optional_timeout(common_args_ethereum())), // Use common_args_ethereum for Ethereum
         )
         .subcommand(
             Command::new("ethereum-solana")
                 .about("Ethereum -> Solana cross-domain intent")
                 .args(cross_domain_args_ethereum()), // Use Ethereum cross domain args
         )
+        .subcommand(
+            Command::new("cancel")
+                .about("Cancel a timed out Solana intent and refund token_in")
+                .arg(
+                    Arg::new("intent_id")
+                        .required(true)
+                        .help("Id of the intent to cancel"),
+                )
+                .arg(tx_send_method_arg()),
+        )
+        .subcommand(
+            Command::new("ethereum-cancel")
+                .about("Withdraw the funds of a timed out Ethereum intent")
+                .arg(
+                    Arg::new("intent_id")
+                        .required(true)
+                        .help("Id of the intent returned by escrowFunds"),
+                ),
+        )
+        .subcommand(
+            Command::new("approve")
+                .about("Set the ERC20 allowance of the Escrow contract")
+                .arg(Arg::new("token").required(true).help("ERC20 token address"))
+                .arg(
+                    Arg::new("amount")
+                        .required_unless_present_any(["unlimited", "revoke"])
+                        .help("Allowance, in base units (or tokens with --ui-amounts)"),
+                )
+                .arg(
+                    Arg::new("unlimited")
+                        .long("unlimited")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["amount", "revoke"])
+                        .help("Approve the maximum uint256 allowance"),
+                )
+                .arg(
+                    Arg::new("revoke")
+                        .long("revoke")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("amount")
+                        .help("Set the allowance back to zero"),
+                )
+                .arg(
+                    Arg::new("spender")
+                        .long("spender")
+                        .help("Spender to approve instead of the Escrow contract"),
+                )
+                .arg(ui_amounts_arg()),
+        )
+        .subcommand(
+            Command::new("keys")
+                .about("Inspect the configured keys")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the Solana and Ethereum addresses of the configured keys")
+                        .arg(
+                            Arg::new("count")
+                                .long("count")
+                                .default_value("1")
+                                .value_parser(clap::value_parser!(u32))
+                                .help("Number of consecutive seed phrase accounts to print"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("submit")
+                .about("Submit an intent saved with --save")
+                .arg(
+                    Arg::new("intent_file")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Path to the intent JSON file"),
+                )
+                .arg(tx_send_method_arg())
+                .arg(auto_approve_arg())
+                .arg(reject_fee_on_transfer_arg())
+                .arg(max_bridge_fee_arg())
+                .arg(bridge_fee_arg()),
+        )
+        .subcommand(
+            Command::new("build")
+                .about("Build an unsigned escrow transaction from a saved intent to sign offline")
+                .arg(
+                    Arg::new("intent_file")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Path to the intent JSON file"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("File the unsigned transaction is written to"),
+                )
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .help("Sender of the intent, defaults to the configured key"),
+                )
+                .arg(
+                    Arg::new("fee_payer_address")
+                        .long("fee-payer-address")
+                        .help(
+                            "Solana fee payer, defaults to the configured fee payer or the sender",
+                        ),
+                )
+                .arg(
+                    Arg::new("nonce_account")
+                        .long("nonce-account")
+                        .help("Solana durable nonce account, so the transaction does not expire"),
+                )
+                .arg(
+                    Arg::new("nonce_authority")
+                        .long("nonce-authority")
+                        .requires("nonce_account")
+                        .help("Authority of the nonce account, defaults to the fee payer"),
+                )
+                .arg(tx_send_method_arg())
+                .arg(reject_fee_on_transfer_arg())
+                .arg(max_bridge_fee_arg())
+                .arg(bridge_fee_arg()),
+        )
+        .subcommand(
+            Command::new("sign")
+                .about("Sign a transaction file written by `build`, in place")
+                .arg(transaction_file_arg())
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .action(ArgAction::SetTrue)
+                        .help("Sign without asking for confirmation"),
+                ),
+        )
+        .subcommand(
+            Command::new("broadcast")
+                .about("Send a transaction file signed with `sign` and notify the auctioneer")
+                .arg(transaction_file_arg()),
+        )
         .get_matches()
 }
 
-/// Parse Solana common arguments.
-pub fn parse_common_args(matches: &ArgMatches) -> (u64, Pubkey, String, String, u64) {
-    let amount_in: u64 = *matches
-        .get_one::<u64>("amount_in")
-        .expect("amount_in is required");
-    let token_in = Pubkey::from_str(matches.get_one::<String>("token_in").unwrap())
-        .expect("Invalid token_in address");
-    let token_out = matches.get_one::<String>("token_out").unwrap().to_string();
-    let amount_out = matches.get_one::<String>("amount_out").unwrap().to_string();
-    let timeout_duration: u64 = *matches
+/// Build the intent described by the positional arguments of an intent subcommand.
+///
+/// With `--ui-amounts` the amounts are human amounts, scaled by the decimals
+/// of `token_in` and `token_out` as read from chain. Token aliases and the
+/// default timeout come from `profile`.
+pub async fn parse_intent_request(
+    client: &MantisClient,
+    profile: &Profile,
+    matches: &ArgMatches,
+    src_domain: Domain,
+    dst_domain: Domain,
+) -> Result<IntentRequest> {
+    let token_in =
+        profile.resolve_token(src_domain, matches.get_one::<String>("token_in").unwrap());
+    let token_out =
+        profile.resolve_token(dst_domain, matches.get_one::<String>("token_out").unwrap());
+    let timeout = matches
         .get_one::<u64>("timeout")
-        .expect("timeout is required");
+        .copied()
+        .or(profile.default_timeout)
+        .ok_or_else(|| {
+            anyhow!("timeout is required, pass it or set default_timeout in the profile")
+        })?;
+    let amount_in = matches.get_one::<String>("amount_in").unwrap();
+    let amount_out = matches.get_one::<String>("amount_out").unwrap();
 
-    (amount_in, token_in, token_out, amount_out, timeout_duration)
+    let (amount_in, amount_out) = if matches.get_flag("ui_amounts") {
+        let amount_in = client
+            .ui_amount(&DomainAddress::parse(src_domain, token_in)?, amount_in)
+            .await?;
+        let amount_out = client
+            .ui_amount(&DomainAddress::parse(dst_domain, token_out)?, amount_out)
+            .await?;
+        print_amount_preview("Amount in", &amount_in);
+        print_amount_preview("Amount out", &amount_out);
+        (amount_in, amount_out)
+    } else {
+        (
+            TokenAmount::parse_raw(amount_in)?,
+            TokenAmount::parse_raw(amount_out)?,
+        )
+    };
+
+    let mut builder = IntentRequest::builder(src_domain, dst_domain)
+        .token_in(token_in)
+        .amount_in(amount_in)
+        .token_out(token_out)
+        .amount_out(amount_out)
+        .timeout(timeout);
+
+    if src_domain != dst_domain {
+        // Without dst_user (or with `self`) the intent pays out to our own
+        // address on the destination chain.
+        let dst_user = match matches.try_get_one::<String>("dst_user").ok().flatten() {
+            Some(dst_user) if dst_user != "self" => dst_user.clone(),
+            _ => {
+                let own_address = client.own_address(dst_domain)?;
+                println!("dst_user: {} (own {} address)", own_address, dst_domain);
+                own_address.to_string()
+            }
+        };
+        builder = builder.dst_user(dst_user);
+    }
+    if let Some(winner_solver) = matches
+        .try_get_one::<String>("winner_solver")
+        .ok()
+        .flatten()
+    {
+        builder = builder.winner_solver(winner_solver);
+    }
+
+    builder.build()
+}
+
+fn print_amount_preview(label: &str, amount: &TokenAmount) {
+    println!(
+        "{}: {} ({} base units, {} decimals)",
+        label,
+        amount.to_ui_string().unwrap_or_default(),
+        amount,
+        amount.decimals().unwrap_or_default()
+    );
+}
+
+/// Interpret amounts as human amounts instead of base units.
+fn ui_amounts_arg() -> Arg {
+    Arg::new("ui_amounts")
+        .long("ui-amounts")
+        .action(ArgAction::SetTrue)
+        .help("Treat amounts as token amounts (e.g. 1.5) scaled by the on-chain decimals")
+}
+
+/// Transaction send method, only relevant for intents escrowed on Solana.
+fn tx_send_method_arg() -> Arg {
+    Arg::new("tx_send_method")
+        .required(false)
+        .long("tx-send-method")
+        .default_value(&TxSendMethod::JITO)
+        .value_parser(clap::value_parser!(TxSendMethod))
+        .help("Transaction send method (jito/jito_http/rpc)")
+}
+
+/// Approve the escrow contract automatically when the allowance is too low.
+fn auto_approve_arg() -> Arg {
+    Arg::new("auto_approve")
+        .long("auto-approve")
+        .action(ArgAction::SetTrue)
+        .help("Approve amount_in to the Escrow contract if the allowance is too low")
+}
+
+/// Stop instead of warning when `token_in` charges a fee on transfer.
+fn reject_fee_on_transfer_arg() -> Arg {
+    Arg::new("reject_fee_on_transfer")
+        .long("reject-fee-on-transfer")
+        .action(ArgAction::SetTrue)
+        .help("Abort if the Escrow contract would receive less than amount_in of token_in")
+}
+
+/// Cap on the cross-domain fee of Ethereum intents, in ETH.
+fn max_bridge_fee_arg() -> Arg {
+    Arg::new("max_bridge_fee")
+        .long("max-bridge-fee")
+        .value_parser(|fee: &str| TokenAmount::parse_ui(fee, 18).map(|fee| fee.raw()))
+        .help("Abort if the cross-domain fee exceeds this (in ETH)")
+}
+
+/// Cross-domain fee of Ethereum intents, in ETH, for escrow contracts that do
+/// not quote one.
+fn bridge_fee_arg() -> Arg {
+    Arg::new("bridge_fee")
+        .long("bridge-fee")
+        .value_parser(|fee: &str| TokenAmount::parse_ui(fee, 18).map(|fee| fee.raw()))
+        .help("Cross-domain fee to pay if the Escrow contract has no crossDomainFee (in ETH)")
+}
+
+/// Transaction file exchanged by `build`, `sign` and `broadcast`.
+fn transaction_file_arg() -> Arg {
+    Arg::new("transaction_file")
+        .required(true)
+        .value_parser(clap::value_parser!(PathBuf))
+        .help("Path to the transaction JSON file")
+}
+
+/// Optional path the intent is written to before it is submitted.
+fn save_arg() -> Arg {
+    Arg::new("save")
+        .required(false)
+        .long("save")
+        .value_parser(clap::value_parser!(PathBuf))
+        .help("Save the intent as JSON to this path")
+}
+
+/// Single domain intents may leave the timeout to the profile's
+/// `default_timeout`. Cross-domain intents keep it required, as `dst_user`
+/// follows it.
+fn with_optional_timeout(args: Vec<Arg>) -> Vec<Arg> {
+    args.into_iter()
+        .map(|arg| {
+            if arg.get_id() == "timeout" {
+                arg.required(false)
+            } else {
+                arg
+            }
+        })
+        .collect()
 }
 
 /// Solana common arguments for single and cross-domain intents.
@@ -51,8 +488,8 @@ pub fn common_args() -> Vec<Arg> {
     vec![
         Arg::new("amount_in")
             .required(true)
-            .value_parser(clap::value_parser!(u64))
-            .help("Amount in tokens"),
+            .value_parser(clap::value_parser!(String))
+            .help("Amount in, in base units (or tokens with --ui-amounts)"),
         Arg::new("token_in")
             .required(true)
             .value_parser(clap::value_parser!(String))
@@ -64,17 +501,14 @@ pub fn common_args() -> Vec<Arg> {
         Arg::new("amount_out")
             .required(true)
             .value_parser(clap::value_parser!(String))
-            .help("Amount out in tokens"),
+            .help("Amount out, in base units (or tokens with --ui-amounts)"),
         Arg::new("timeout")
             .required(true)
             .value_parser(clap::value_parser!(u64))
             .help("Timeout duration in seconds"),
-        Arg::new("tx_send_method")
-            .required(false)
-            .long("tx-send-method")
-            .default_value(&TxSendMethod::JITO)
-            .value_parser(clap::value_parser!(TxSendMethod))
-            .help("Transaction send method (RPC/JITO)"),
+        tx_send_method_arg(),
+        ui_amounts_arg(),
+        save_arg(),
     ]
 }
 
@@ -83,8 +517,8 @@ fn cross_domain_args() -> Vec<Arg> {
     let mut args = common_args();
     args.push(
         Arg::new("dst_user")
-            .required(true)
-            .help("Destination user address"),
+            .required(false)
+            .help("Destination user address, defaults to your own address (`self`)"),
     );
     args
 }
@@ -97,19 +531,27 @@ fn common_args_ethereum() -> Vec<Arg> {
             .help("Token input address"),
         Arg::new("amount_in")
             .required(true)
-            .value_parser(clap::value_parser!(u64))
-            .help("Amount in tokens"),
+            .value_parser(clap::value_parser!(String))
+            .help("Amount in, in base units (or tokens with --ui-amounts)"),
         Arg::new("token_out")
             .required(true)
             .help("Token output address"),
         Arg::new("amount_out")
             .required(true)
-            .value_parser(clap::value_parser!(u64))
-            .help("Amount out in tokens"),
+            .value_parser(clap::value_parser!(String))
+            .help("Amount out, in base units (or tokens with --ui-amounts)"),
         Arg::new("timeout")
             .required(true)
             .value_parser(clap::value_parser!(u64))
             .help("Timeout duration in seconds"),
+        Arg::new("winner_solver")
+            .required(false)
+            .long("winner-solver")
+            .help("Solver allowed to fill the intent"),
+        ui_amounts_arg(),
+        auto_approve_arg(),
+        reject_fee_on_transfer_arg(),
+        save_arg(),
     ]
 }
 
@@ -118,8 +560,10 @@ fn cross_domain_args_ethereum() -> Vec<Arg> {
     let mut args = common_args_ethereum();
     args.push(
         Arg::new("dst_user")
-            .required(true)
-            .help("Destination user address"),
+            .required(false)
+            .help("Destination user address, defaults to your own address (`self`)"),
     );
+    args.push(max_bridge_fee_arg());
+    args.push(bridge_fee_arg());
     args
 }
@@ -0,0 +1,467 @@
+use std::sync::Arc;
+
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::Client;
+use anyhow::{anyhow, Result};
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{Address, TransactionReceipt, U256};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::{Signature, Signer};
+
+use crate::address::DomainAddress;
+use crate::amount::TokenAmount;
+use crate::auctioneer::send_signature_to_auctioneer;
+use crate::config::Profile;
+use crate::ethereum::{
+    approve_erc20, build_escrow_intent_ethereum, connect_ethereum_signer,
+    escrow_and_store_intent_ethereum, escrowed_intent_id, fetch_cross_domain_fee,
+    fetch_erc20_decimals, user_cancel_intent_ethereum, ApprovalAmount, EthereumSigner,
+};
+use crate::generate_random_intent_id;
+use crate::intent::{Domain, EscrowOptions, IntentRequest};
+use crate::network::Network;
+use crate::offline::OfflineTransaction;
+use crate::signer::{MantisEthereumSigner, MantisSolanaSigner};
+use crate::solana::{
+    build_escrow_intent_solana, escrow_and_store_intent_solana, fetch_mint_decimals,
+    send_signed_transaction, user_cancel_intent_solana, DurableNonce, SolanaSigners, SubmitConfig,
+    SubmitResult, TxSendMethod,
+};
+
+/// Solana side of a [`MantisClient`].
+struct SolanaConnection {
+    signers: SolanaSigners,
+    client: Client<Arc<MantisSolanaSigner>>,
+}
+
+/// Outcome of escrowing an intent on its source domain.
+#[derive(Debug, Clone)]
+pub enum EscrowReceipt {
+    Solana {
+        intent_id: String,
+        signature: Signature,
+        /// Jito tip paid in lamports, `None` when sent over RPC.
+        jito_tip: Option<u64>,
+    },
+    Ethereum {
+        /// Id to pass to `ethereum-cancel`, `None` when the transaction has
+        /// no `FundsEscrowed` log.
+        intent_id: Option<U256>,
+        receipt: TransactionReceipt,
+    },
+}
+
+/// Long-lived entry point to the SDK.
+///
+/// Holds the Solana and Ethereum signers together with their RPC connections
+/// and the [`Network`] they belong to, so intents can be submitted repeatedly
+/// without reconnecting. Either domain may be left unconfigured; calls that
+/// need it then fail with an error instead of panicking.
+pub struct MantisClient {
+    network: Network,
+    submit_config: SubmitConfig,
+    solana: Option<SolanaConnection>,
+    ethereum_provider: Option<Arc<Provider<Http>>>,
+    ethereum: Option<Arc<EthereumSigner>>,
+}
+
+impl MantisClient {
+    pub fn new(network: Network) -> Self {
+        Self {
+            submit_config: SubmitConfig::new(&network),
+            network,
+            solana: None,
+            ethereum_provider: None,
+            ethereum: None,
+        }
+    }
+
+    /// Build a client for `network` from the `SOLANA_KEYPAIR` (or
+    /// `SOLANA_KEYPAIR_PATH`), `ETHEREUM_PKEY` (or `ETHEREUM_KEYSTORE`) and
+    /// `ETHEREUM_RPC` environment variables, applying the overrides of
+    /// [`Network::with_env_overrides`].
+    ///
+    /// A domain's signer is only configured when its key variable is set.
+    /// The RPC endpoints are used for read-only queries either way.
+    pub async fn from_env(network: Network) -> Result<Self> {
+        Self::from_profile(network, &Profile::default().with_env_overrides()).await
+    }
+
+    /// Build a client for `network` configured by `profile`.
+    ///
+    /// Apply [`Profile::with_env_overrides`] first for the environment to
+    /// take precedence over the profile, as the CLI does.
+    pub async fn from_profile(network: Network, profile: &Profile) -> Result<Self> {
+        let network = profile.apply_to_network(network)?.with_env_overrides()?;
+        let submit_config = profile.apply_to_submit_config(SubmitConfig::new(&network))?;
+        let mut client = Self::new(network).with_submit_config(submit_config);
+
+        if let Some(source) = &profile.solana_key {
+            client = client.with_solana(source.solana_signer()?);
+        }
+        if let Some(source) = &profile.solana_fee_payer {
+            client = client.with_solana_fee_payer(source.solana_signer()?)?;
+        }
+
+        if let Some(rpc_url) = &profile.ethereum_rpc {
+            let provider = Provider::<Http>::try_from(rpc_url.as_str())?;
+            client.ethereum_provider = Some(Arc::new(provider));
+        }
+
+        if let Some(source) = &profile.ethereum_key {
+            let rpc_url = profile.ethereum_rpc.as_ref().ok_or_else(|| {
+                anyhow!("ETHEREUM_RPC must be set when an Ethereum key is configured")
+            })?;
+            client = client
+                .with_ethereum(rpc_url, source.ethereum_signer()?)
+                .await?;
+        }
+
+        Ok(client)
+    }
+
+    /// Configure the Solana signer, a `Keypair` or a remote signer, talking
+    /// to the cluster of the network.
+    pub fn with_solana(mut self, wallet: impl Into<MantisSolanaSigner>) -> Self {
+        let wallet = Arc::new(wallet.into());
+        let signers = SolanaSigners::new(wallet.clone());
+        let client = Client::new_with_options(
+            self.network.solana_cluster.clone(),
+            wallet.clone(),
+            CommitmentConfig::processed(),
+        );
+        self.solana = Some(SolanaConnection { signers, client });
+        self
+    }
+
+    /// Pay Solana transaction fees, Jito tips and token account rent from
+    /// `fee_payer` instead of the Solana signer, which then only signs as
+    /// the owner of the escrowed tokens.
+    ///
+    /// Fails when the Solana signer is not configured yet.
+    pub fn with_solana_fee_payer(
+        mut self,
+        fee_payer: impl Into<MantisSolanaSigner>,
+    ) -> Result<Self> {
+        let solana = self
+            .solana
+            .as_mut()
+            .ok_or_else(|| anyhow!("Configure the Solana signer before its fee payer"))?;
+        solana.signers = solana
+            .signers
+            .clone()
+            .with_fee_payer(Arc::new(fee_payer.into()));
+        Ok(self)
+    }
+
+    /// Configure the Ethereum signer, a `LocalWallet` or a remote signer, and
+    /// the node it talks to.
+    ///
+    /// Fails when the node is not on the chain expected by the network.
+    pub async fn with_ethereum(
+        mut self,
+        rpc_url: &str,
+        wallet: impl Into<MantisEthereumSigner>,
+    ) -> Result<Self> {
+        self.ethereum_provider = Some(Arc::new(Provider::<Http>::try_from(rpc_url)?));
+        self.ethereum = Some(connect_ethereum_signer(rpc_url, wallet.into(), &self.network).await?);
+        Ok(self)
+    }
+
+    /// Override how Solana transactions are sent.
+    pub fn with_submit_config(mut self, submit_config: SubmitConfig) -> Self {
+        self.submit_config = submit_config;
+        self
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    pub fn submit_config(&self) -> &SubmitConfig {
+        &self.submit_config
+    }
+
+    pub fn solana_pubkey(&self) -> Result<Pubkey> {
+        Ok(self.solana()?.signers.owner.pubkey())
+    }
+
+    pub fn ethereum_address(&self) -> Result<Address> {
+        Ok(self.ethereum()?.address())
+    }
+
+    /// Address of the configured signer of `domain`.
+    pub fn own_address(&self, domain: Domain) -> Result<DomainAddress> {
+        Ok(match domain {
+            Domain::Solana => DomainAddress::Solana(self.solana_pubkey()?),
+            Domain::Ethereum => DomainAddress::Ethereum(self.ethereum_address()?),
+        })
+    }
+
+    fn solana(&self) -> Result<&SolanaConnection> {
+        self.solana
+            .as_ref()
+            .ok_or_else(|| anyhow!("Solana signer is not configured, set SOLANA_KEYPAIR"))
+    }
+
+    /// RPC client for the configured Solana cluster, usable without a signer.
+    pub fn solana_rpc(&self) -> RpcClient {
+        RpcClient::new_with_commitment(
+            self.network.solana_cluster.url().to_string(),
+            CommitmentConfig::processed(),
+        )
+    }
+
+    /// Ethereum provider, usable without a signer.
+    pub fn ethereum_provider(&self) -> Result<Arc<Provider<Http>>> {
+        self.ethereum_provider
+            .clone()
+            .ok_or_else(|| anyhow!("Ethereum RPC is not configured, set ETHEREUM_RPC"))
+    }
+
+    fn ethereum(&self) -> Result<&Arc<EthereumSigner>> {
+        self.ethereum.as_ref().ok_or_else(|| {
+            anyhow!("Ethereum signer is not configured, set ETHEREUM_PKEY or ETHEREUM_KEYSTORE")
+        })
+    }
+
+    /// Decimals of `token`, read from the SPL mint or the ERC20 contract.
+    pub async fn token_decimals(&self, token: &DomainAddress) -> Result<u8> {
+        match token {
+            DomainAddress::Solana(mint) => fetch_mint_decimals(&self.solana_rpc(), mint).await,
+            DomainAddress::Ethereum(token) => {
+                fetch_erc20_decimals(self.ethereum_provider()?, *token).await
+            }
+        }
+    }
+
+    /// Parse a human amount such as `1.5` using the on-chain decimals of `token`.
+    pub async fn ui_amount(&self, token: &DomainAddress, amount: &str) -> Result<TokenAmount> {
+        let decimals = self.token_decimals(token).await?;
+        TokenAmount::parse_ui(amount, decimals)
+    }
+
+    /// Fee in wei paid to the Ethereum escrow contract for cross-domain intents,
+    /// `bridge_fee` or the network's default when the contract does not quote
+    /// one.
+    pub async fn cross_domain_fee(&self, bridge_fee: Option<U256>) -> Result<U256> {
+        fetch_cross_domain_fee(self.ethereum_provider()?, &self.network, bridge_fee).await
+    }
+
+    /// Escrow the funds of `intent` on its source domain and store the intent.
+    pub async fn escrow_intent(
+        &self,
+        intent: &IntentRequest,
+        options: &EscrowOptions,
+    ) -> Result<EscrowReceipt> {
+        intent.validate()?;
+
+        match intent.src_domain {
+            Domain::Solana => {
+                let solana = self.solana()?;
+                let intent_id = generate_random_intent_id();
+
+                let result = escrow_and_store_intent_solana(
+                    &solana.signers,
+                    &solana.client,
+                    &self.network,
+                    &self.submit_config,
+                    intent_id.clone(),
+                    intent,
+                    options.tx_send_method,
+                )
+                .await
+                .map_err(|e| anyhow!(e))?;
+
+                Ok(EscrowReceipt::Solana {
+                    intent_id,
+                    signature: result.signature,
+                    jito_tip: result.jito_tip,
+                })
+            }
+            Domain::Ethereum => {
+                let (intent_id, receipt) = escrow_and_store_intent_ethereum(
+                    self.ethereum()?,
+                    &self.network,
+                    intent,
+                    options,
+                )
+                .await?;
+                Ok(EscrowReceipt::Ethereum { intent_id, receipt })
+            }
+        }
+    }
+
+    /// Escrow `intent` and, for Solana, report the transaction to the auctioneer.
+    pub async fn submit_intent(
+        &self,
+        intent: &IntentRequest,
+        options: &EscrowOptions,
+    ) -> Result<EscrowReceipt> {
+        let receipt = self.escrow_intent(intent, options).await?;
+        if let EscrowReceipt::Solana { signature, .. } = &receipt {
+            self.send_signature_to_auctioneer(*signature).await?;
+        }
+        Ok(receipt)
+    }
+
+    /// Build the unsigned escrow transaction of `intent` for `from`, by default
+    /// the configured signer, to be signed with [`OfflineTransaction`] on
+    /// another machine.
+    ///
+    /// Only `tx_send_method`, `reject_fee_on_transfer`, `max_bridge_fee` and
+    /// `bridge_fee` of `options` apply. A Solana `fee_payer` defaults to the
+    /// configured fee payer, or `from`. `nonce` keeps a Solana transaction
+    /// valid until it is broadcast.
+    pub async fn build_intent(
+        &self,
+        intent: &IntentRequest,
+        from: Option<DomainAddress>,
+        fee_payer: Option<Pubkey>,
+        options: &EscrowOptions,
+        nonce: Option<DurableNonce>,
+    ) -> Result<OfflineTransaction> {
+        intent.validate()?;
+        let from = match from {
+            Some(from) => from,
+            None => self.own_address(intent.src_domain)?,
+        };
+
+        match intent.src_domain {
+            Domain::Solana => {
+                let jito_tip = match options.tx_send_method.is_jito() {
+                    true => Some(self.submit_config.jito_tip(0).await),
+                    false => None,
+                };
+                let from = from.as_solana()?;
+                let fee_payer = match (fee_payer, &self.solana) {
+                    (Some(fee_payer), _) => fee_payer,
+                    (None, Some(solana)) if solana.signers.owner.pubkey() == from => {
+                        solana.signers.fee_payer.pubkey()
+                    }
+                    (None, _) => from,
+                };
+                let (payload, transaction) = build_escrow_intent_solana(
+                    &self.solana_rpc(),
+                    self.network.bridge_escrow_program,
+                    from,
+                    fee_payer,
+                    intent,
+                    jito_tip,
+                    &self.submit_config.priority_fee,
+                    nonce,
+                )
+                .await?;
+                OfflineTransaction::solana(
+                    &payload,
+                    options.tx_send_method,
+                    jito_tip,
+                    nonce.map(|nonce| nonce.account.to_string()),
+                    &transaction,
+                )
+            }
+            Domain::Ethereum => {
+                let provider = self.ethereum_provider()?;
+                let chain_id = self
+                    .network
+                    .check_ethereum_chain_id(provider.get_chainid().await?.as_u64())?;
+                let (info, transaction) = build_escrow_intent_ethereum(
+                    provider,
+                    &self.network,
+                    chain_id,
+                    from.as_ethereum()?,
+                    intent,
+                    options,
+                )
+                .await?;
+                Ok(OfflineTransaction::ethereum(&info, chain_id, transaction))
+            }
+        }
+    }
+
+    /// Send a transaction signed offline and, for Solana, report it to the
+    /// auctioneer.
+    pub async fn broadcast(&self, transaction: &OfflineTransaction) -> Result<EscrowReceipt> {
+        if !transaction.is_signed()? {
+            return Err(anyhow!("Transaction is not signed yet"));
+        }
+
+        match transaction {
+            OfflineTransaction::Solana {
+                intent_id,
+                jito_tip,
+                ..
+            } => {
+                let signature = send_signed_transaction(
+                    &self.solana_rpc(),
+                    &self.submit_config,
+                    &transaction.solana_transaction()?,
+                    transaction.tx_send_method()?,
+                )
+                .await?;
+                self.send_signature_to_auctioneer(signature).await?;
+                Ok(EscrowReceipt::Solana {
+                    intent_id: intent_id.clone(),
+                    signature,
+                    jito_tip: *jito_tip,
+                })
+            }
+            OfflineTransaction::Ethereum { signed_rlp, .. } => {
+                let provider = self.ethereum_provider()?;
+                let signed_rlp = signed_rlp.clone().unwrap_or_default();
+                let receipt = provider
+                    .send_raw_transaction(signed_rlp)
+                    .await?
+                    .await?
+                    .ok_or_else(|| anyhow!("Failed to fetch transaction receipt"))?;
+
+                let intent_id = escrowed_intent_id(self.network.escrow_contract()?, &receipt);
+                Ok(EscrowReceipt::Ethereum { intent_id, receipt })
+            }
+        }
+    }
+
+    /// Cancel a timed out Solana intent and refund its escrowed tokens.
+    pub async fn cancel_intent_solana(
+        &self,
+        intent_id: &str,
+        tx_send_method: TxSendMethod,
+    ) -> Result<SubmitResult> {
+        let solana = self.solana()?;
+        user_cancel_intent_solana(
+            &solana.signers,
+            &solana.client,
+            &self.network,
+            &self.submit_config,
+            intent_id,
+            tx_send_method,
+        )
+        .await
+    }
+
+    /// Set the allowance of `spender` for `token`, by default the escrow contract.
+    pub async fn approve_erc20(
+        &self,
+        token: Address,
+        spender: Option<Address>,
+        amount: ApprovalAmount,
+    ) -> Result<TransactionReceipt> {
+        let spender = match spender {
+            Some(spender) => spender,
+            None => self.network.escrow_contract()?,
+        };
+        approve_erc20(self.ethereum()?, token, spender, amount).await
+    }
+
+    /// Withdraw the escrowed funds of a timed out Ethereum intent.
+    pub async fn cancel_intent_ethereum(&self, intent_id: U256) -> Result<TransactionReceipt> {
+        user_cancel_intent_ethereum(self.ethereum()?, self.network.escrow_contract()?, intent_id)
+            .await
+    }
+
+    /// Notify the auctioneer about a Solana escrow transaction.
+    pub async fn send_signature_to_auctioneer(&self, signature: Signature) -> Result<()> {
+        send_signature_to_auctioneer(self.network.auctioneer_url()?, signature).await
+    }
+}
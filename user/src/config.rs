@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::Cluster;
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+
+use crate::intent::Domain;
+use crate::jito::{resolve_jito_regions, JitoBlockEngines};
+use crate::keys::KeySource;
+use crate::network::{Network, NetworkName};
+use crate::solana::SubmitConfig;
+
+/// Config file read when `--config` and `MANTIS_CONFIG` are not given.
+pub const DEFAULT_CONFIG_PATH: &str = "mantis.toml";
+
+/// Profile used when `--profile` is not given.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Contents of `mantis.toml`.
+///
+/// ```toml
+/// [profiles.default]
+/// network = "mainnet"
+/// ethereum_rpc = "https://eth.llamarpc.com"
+/// solana_key = { env = "SOLANA_KEYPAIR" }
+///
+/// [profiles.default.tokens.solana]
+/// usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// Named set of settings. Every field is optional and falls back to the
+/// environment and then to the defaults of the selected network.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub network: Option<NetworkName>,
+    pub solana_rpc: Option<String>,
+    pub ethereum_rpc: Option<String>,
+    pub ethereum_chain_id: Option<u64>,
+    pub escrow_contract: Option<String>,
+    pub bridge_escrow_program: Option<String>,
+    pub auctioneer_url: Option<String>,
+    pub solana_key: Option<KeySource>,
+    pub ethereum_key: Option<KeySource>,
+    /// Key paying Solana fees, tips and rent instead of `solana_key`.
+    pub solana_fee_payer: Option<KeySource>,
+    #[serde(default)]
+    pub jito: JitoConfig,
+    #[serde(default)]
+    pub priority_fee: PriorityFeeProfile,
+    /// Attempts before a Solana transaction is given up.
+    pub retries: Option<u8>,
+    /// Timeout in seconds used when an intent does not give one.
+    pub default_timeout: Option<u64>,
+    #[serde(default)]
+    pub tokens: TokenAliases,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JitoConfig {
+    pub block_engine_url: Option<String>,
+    /// Tip in lamports, the lowest tip with a tip strategy.
+    pub tip_amount: Option<u64>,
+    /// `fixed`, `percentile:<p>` or `escalate:<step>`.
+    pub tip_strategy: Option<String>,
+    /// Highest tip in lamports a tip strategy may pay.
+    pub max_tip_amount: Option<u64>,
+    pub tip_floor_url: Option<String>,
+    /// Comma separated region names or URLs replacing `block_engine_url`,
+    /// see [`crate::jito::resolve_jito_regions`].
+    pub regions: Option<String>,
+    /// Regions raced per bundle, the ones with the lowest latency.
+    pub race: Option<usize>,
+    /// Whitelisted key authenticating with the searcher API for `jito`,
+    /// the fee payer's key when not set.
+    pub auth_key: Option<KeySource>,
+}
+
+/// Solana compute budget, see [`crate::solana::PriorityFeeConfig`].
+///
+/// ```toml
+/// [profiles.default.priority_fee]
+/// min_price = 1000
+/// max_price = 200000
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriorityFeeProfile {
+    /// Fixed price in micro-lamports per compute unit.
+    pub price: Option<u64>,
+    pub min_price: Option<u64>,
+    pub max_price: Option<u64>,
+    /// Percentile of recent prioritization fees paid without a fixed price.
+    pub percentile: Option<u8>,
+    /// Fixed compute unit limit, sized by simulation otherwise.
+    pub compute_unit_limit: Option<u32>,
+}
+
+/// Short names for token addresses, per domain.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenAliases {
+    #[serde(default)]
+    pub solana: HashMap<String, String>,
+    #[serde(default)]
+    pub ethereum: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let toml = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read config file {}: {}", path.display(), e))?;
+        toml::from_str(&toml).map_err(|e| anyhow!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    /// Load the config at `path`, or `mantis.toml` if it exists.
+    ///
+    /// A missing default file yields an empty config, a missing explicit
+    /// file is an error.
+    pub fn load_or_default(path: Option<PathBuf>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::load(DEFAULT_CONFIG_PATH),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Profile `name`, or the `default` profile (possibly empty) when no
+    /// name is given.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Profile {} is not defined in the config file", name)),
+            None => Ok(self
+                .profiles
+                .get(DEFAULT_PROFILE)
+                .cloned()
+                .unwrap_or_default()),
+        }
+    }
+}
+
+impl Profile {
+    /// Replace the RPC endpoint and keys with the ones set in the
+    /// environment: `ETHEREUM_RPC`, `SOLANA_KEYPAIR` or `SOLANA_KEYPAIR_PATH`,
+    /// `SOLANA_FEE_PAYER` or `SOLANA_FEE_PAYER_PATH`, `JITO_AUTH_KEYPAIR` or
+    /// `JITO_AUTH_KEYPAIR_PATH`, and `ETHEREUM_PKEY` or `ETHEREUM_KEYSTORE`
+    /// (with an optional `ETHEREUM_PASSWORD_FILE`). `JITO_BLOCK_ENGINE_URL`
+    /// replaces the profile's Jito regions.
+    pub fn with_env_overrides(self) -> Self {
+        self.with_overrides(|name| env::var_os(name))
+    }
+
+    /// [`Profile::with_env_overrides`] with the variables looked up by `var`.
+    fn with_overrides(mut self, var: impl Fn(&str) -> Option<OsString>) -> Self {
+        if let Some(rpc_url) = var("ETHEREUM_RPC").and_then(|url| url.into_string().ok()) {
+            self.ethereum_rpc = Some(rpc_url);
+        }
+
+        if var("SOLANA_KEYPAIR").is_some() {
+            self.solana_key = Some(KeySource::Env("SOLANA_KEYPAIR".to_string()));
+        } else if let Some(path) = var("SOLANA_KEYPAIR_PATH") {
+            self.solana_key = Some(KeySource::File(PathBuf::from(path)));
+        }
+
+        if var("SOLANA_FEE_PAYER").is_some() {
+            self.solana_fee_payer = Some(KeySource::Env("SOLANA_FEE_PAYER".to_string()));
+        } else if let Some(path) = var("SOLANA_FEE_PAYER_PATH") {
+            self.solana_fee_payer = Some(KeySource::File(PathBuf::from(path)));
+        }
+
+        if var("JITO_AUTH_KEYPAIR").is_some() {
+            self.jito.auth_key = Some(KeySource::Env("JITO_AUTH_KEYPAIR".to_string()));
+        } else if let Some(path) = var("JITO_AUTH_KEYPAIR_PATH") {
+            self.jito.auth_key = Some(KeySource::File(PathBuf::from(path)));
+        }
+
+        if var("JITO_BLOCK_ENGINE_URL").is_some() {
+            self.jito.regions = None;
+        }
+
+        if var("ETHEREUM_PKEY").is_some() {
+            self.ethereum_key = Some(KeySource::Env("ETHEREUM_PKEY".to_string()));
+        } else if let Some(path) = var("ETHEREUM_KEYSTORE") {
+            self.ethereum_key = Some(KeySource::Keystore {
+                path: PathBuf::from(path),
+                password_file: var("ETHEREUM_PASSWORD_FILE").map(PathBuf::from),
+                password_fd: None,
+            });
+        }
+
+        self
+    }
+
+    /// Apply the profile's endpoints and deployments on top of `network`.
+    pub fn apply_to_network(&self, mut network: Network) -> Result<Network> {
+        if let Some(rpc_url) = &self.solana_rpc {
+            network.solana_cluster = Cluster::from_str(rpc_url)?;
+        }
+        if let Some(chain_id) = self.ethereum_chain_id {
+            network.ethereum_chain_id = Some(chain_id);
+        }
+        if let Some(escrow) = &self.escrow_contract {
+            network.escrow_contract = Some(
+                escrow
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid escrow_contract {}: {}", escrow, e))?,
+            );
+        }
+        if let Some(program) = &self.bridge_escrow_program {
+            network.bridge_escrow_program = Pubkey::from_str(program)
+                .map_err(|e| anyhow!("Invalid bridge_escrow_program {}: {}", program, e))?;
+        }
+        if let Some(auctioneer_url) = &self.auctioneer_url {
+            network.auctioneer_url = Some(auctioneer_url.clone());
+        }
+        if let Some(block_engine_url) = &self.jito.block_engine_url {
+            network.jito_block_engine_url = Some(block_engine_url.clone());
+        }
+        Ok(network)
+    }
+
+    /// Apply the profile's Jito, retry and priority fee settings on top of
+    /// `config`.
+    pub fn apply_to_submit_config(&self, mut config: SubmitConfig) -> Result<SubmitConfig> {
+        if let Some(tip_amount) = self.jito.tip_amount {
+            config.jito_tip_amount = tip_amount;
+        }
+        if let Some(tip_strategy) = &self.jito.tip_strategy {
+            config.jito_tip_strategy = tip_strategy.parse()?;
+        }
+        if let Some(max_tip_amount) = self.jito.max_tip_amount {
+            config.max_jito_tip_amount = max_tip_amount;
+        }
+        if let Some(tip_floor_url) = &self.jito.tip_floor_url {
+            config.jito_tip_floor_url = tip_floor_url.clone();
+        }
+        if let Some(regions) = &self.jito.regions {
+            config.jito_block_engines = JitoBlockEngines::new(resolve_jito_regions(regions)?);
+        }
+        if let Some(race) = self.jito.race {
+            if race == 0 {
+                bail!("jito.race must be at least 1");
+            }
+            config.jito_block_engines.race = Some(race);
+        }
+        if let Some(source) = &self.jito.auth_key {
+            config.jito_auth_keypair = Some(
+                source
+                    .solana_signer()?
+                    .keypair()
+                    .ok_or_else(|| anyhow!("jito.auth_key must be a local keypair"))?,
+            );
+        }
+        if let Some(retries) = self.retries {
+            if retries == 0 {
+                bail!("retries must be at least 1");
+            }
+            config.retries = retries;
+        }
+
+        let priority_fee = &mut config.priority_fee;
+        if let Some(price) = self.priority_fee.price {
+            priority_fee.compute_unit_price = Some(price);
+        }
+        if let Some(min_price) = self.priority_fee.min_price {
+            priority_fee.min_compute_unit_price = min_price;
+        }
+        if let Some(max_price) = self.priority_fee.max_price {
+            priority_fee.max_compute_unit_price = max_price;
+        }
+        if priority_fee.min_compute_unit_price > priority_fee.max_compute_unit_price {
+            bail!("priority_fee.min_price must not exceed priority_fee.max_price");
+        }
+        if let Some(percentile) = self.priority_fee.percentile {
+            if percentile > 100 {
+                bail!("priority_fee.percentile must be at most 100");
+            }
+            priority_fee.percentile = percentile;
+        }
+        if let Some(limit) = self.priority_fee.compute_unit_limit {
+            priority_fee.compute_unit_limit = Some(limit);
+        }
+        Ok(config)
+    }
+
+    /// Resolve a token alias of `domain`, other values are returned as is.
+    pub fn resolve_token<'a>(&'a self, domain: Domain, token: &'a str) -> &'a str {
+        let aliases = match domain {
+            Domain::Solana => &self.tokens.solana,
+            Domain::Ethereum => &self.tokens.ethereum,
+        };
+        aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(token))
+            .map(|(_, address)| address.as_str())
+            .unwrap_or(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), OsString::from(value)))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn block_engine_url_env_replaces_profile_regions() {
+        let profile = Profile {
+            jito: JitoConfig {
+                regions: Some("ny,slc".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let kept = profile.clone().with_overrides(vars(&[]));
+        assert_eq!(kept.jito.regions.as_deref(), Some("ny,slc"));
+
+        let replaced =
+            profile.with_overrides(vars(&[("JITO_BLOCK_ENGINE_URL", "http://127.0.0.1:9100")]));
+        assert_eq!(replaced.jito.regions, None);
+    }
+
+    #[test]
+    fn env_keys_replace_profile_keys() {
+        let profile = Profile {
+            ethereum_rpc: Some("http://profile".to_string()),
+            solana_key: Some(KeySource::File(PathBuf::from("profile.json"))),
+            ..Default::default()
+        };
+
+        let profile = profile.with_overrides(vars(&[
+            ("ETHEREUM_RPC", "http://env"),
+            ("SOLANA_KEYPAIR", "[1,2,3]"),
+            ("SOLANA_KEYPAIR_PATH", "ignored.json"),
+            ("ETHEREUM_KEYSTORE", "keystore.json"),
+            ("ETHEREUM_PASSWORD_FILE", "password.txt"),
+        ]));
+        assert_eq!(profile.ethereum_rpc.as_deref(), Some("http://env"));
+        assert_eq!(
+            profile.solana_key,
+            Some(KeySource::Env("SOLANA_KEYPAIR".to_string()))
+        );
+        assert_eq!(
+            profile.ethereum_key,
+            Some(KeySource::Keystore {
+                path: PathBuf::from("keystore.json"),
+                password_file: Some(PathBuf::from("password.txt")),
+                password_fd: None,
+            })
+        );
+    }
+}
@@ -1,6 +1,12 @@
-use crate::env;
+use crate::intent::{EscrowOptions, IntentRequest};
+use crate::network::Network;
+use crate::signer::MantisEthereumSigner;
+use anyhow::Result;
+use ethers::abi::{AbiDecode, RawLog};
 use ethers::prelude::*;
 use ethers::providers::{Http, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde_json::json;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -30,6 +36,49 @@ abigen!(
         "payable": true,
         "stateMutability": "payable",
         "type": "function"
+    },
+    {
+        "anonymous": false,
+        "inputs": [
+            { "indexed": true, "name": "intentId", "type": "uint256" },
+            { "indexed": true, "name": "srcUser", "type": "address" }
+        ],
+        "name": "FundsEscrowed",
+        "type": "event"
+    },
+    {
+        "constant": false,
+        "inputs": [{ "name": "intentId", "type": "uint256" }],
+        "name": "withdrawFunds",
+        "outputs": [],
+        "payable": false,
+        "stateMutability": "nonpayable",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [{ "name": "", "type": "uint256" }],
+        "name": "intents",
+        "outputs": [
+            { "name": "tokenIn", "type": "address" },
+            { "name": "amountIn", "type": "uint256" },
+            { "name": "srcUser", "type": "address" },
+            { "name": "tokenOut", "type": "string" },
+            { "name": "amountOut", "type": "uint256" },
+            { "name": "dstUser", "type": "string" },
+            { "name": "winnerSolver", "type": "string" },
+            { "name": "timeout", "type": "uint256" }
+        ],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "crossDomainFee",
+        "outputs": [{ "name": "", "type": "uint256" }],
+        "stateMutability": "view",
+        "type": "function"
     }
     ]"#
 );
@@ -46,111 +95,788 @@ abigen!(
         "name": "approve",
         "outputs": [{ "name": "", "type": "bool" }],
         "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [
+            { "name": "_owner", "type": "address" },
+            { "name": "_spender", "type": "address" }
+        ],
+        "name": "allowance",
+        "outputs": [{ "name": "", "type": "uint256" }],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [{ "name": "_owner", "type": "address" }],
+        "name": "balanceOf",
+        "outputs": [{ "name": "balance", "type": "uint256" }],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "decimals",
+        "outputs": [{ "name": "", "type": "uint8" }],
+        "stateMutability": "view",
+        "type": "function"
     }]"#
 );
 
-pub async fn escrow_and_store_intent_ethereum(
-    token_in: Address,
-    amount_in: U256,
-    token_out: String,
-    amount_out: U256,
-    mut dst_user: String,
-    single_domain: bool,
-    timeout: U256,
-) -> Result<TransactionReceipt, Box<dyn std::error::Error>> {
-    let contract_address = "0x64E78873057769a5fd9A2278E6820666ec7e87f9"; // Escrow Contract
-    let private_key = env::var("ETHEREUM_PKEY").expect("ETHEREUM_PKEY must be set");
-    let rpc_url = env::var("ETHEREUM_RPC").expect("ETHEREUM_RPC must be set");
+/// Escrow contract on Ethereum mainnet, see [`Network`] for other networks.
+pub const ESCROW_CONTRACT_ADDRESS: &str = "0x64E78873057769a5fd9A2278E6820666ec7e87f9";
+
+/// Placeholder `token_in` for escrowing native ETH.
+pub const NATIVE_TOKEN_ADDRESS: &str = "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE";
 
+/// Signing middleware used for every Ethereum transaction.
+pub type EthereumSigner = SignerMiddleware<Provider<Http>, MantisEthereumSigner>;
+
+/// Connect `wallet`, local or remote, to the Ethereum node at `rpc_url`.
+///
+/// The chain id is read from the node and checked against `network`.
+pub async fn connect_ethereum_signer(
+    rpc_url: &str,
+    wallet: MantisEthereumSigner,
+    network: &Network,
+) -> Result<Arc<EthereumSigner>> {
     let provider = Provider::<Http>::try_from(rpc_url)?;
-    let provider = Arc::new(provider);
+    let chain_id = provider
+        .get_chainid()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch the chain id from {}: {}", rpc_url, e))?;
+    let chain_id = network.check_ethereum_chain_id(chain_id.as_u64())?;
+    let wallet = wallet.with_chain_id(chain_id);
 
-    let wallet: LocalWallet = private_key.parse()?;
-    let wallet = wallet.with_chain_id(1u64); // Mainnet
-    let wallet = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
-    let src_user = wallet.address();
+    Ok(Arc::new(SignerMiddleware::new(provider, wallet)))
+}
 
-    let contract_address = contract_address.parse::<Address>()?;
+pub async fn escrow_and_store_intent_ethereum(
+    wallet: &Arc<EthereumSigner>,
+    network: &Network,
+    intent: &IntentRequest,
+    options: &EscrowOptions,
+) -> Result<(Option<U256>, TransactionReceipt)> {
+    let token_in = intent.token_in.as_ethereum()?;
+    let amount_in = intent.amount_in.raw();
+    let is_native = token_in == H160::from_str(NATIVE_TOKEN_ADDRESS)?;
+
+    let contract_address = network.escrow_contract()?;
     let contract = Escrow::new(contract_address, wallet.clone());
+    let value = escrow_value(&contract, network, intent, options).await?;
+    let info = escrow_intent_info(wallet.address(), intent)?;
 
-    // Set dst_user based on whether it's a single domain or cross domain transaction
-    if single_domain {
-        dst_user = format!("0x{:x}", src_user);
+    if !is_native {
+        check_transfer_fee(
+            wallet.as_ref(),
+            wallet.address(),
+            contract_address,
+            token_in,
+            amount_in,
+            options.reject_fee_on_transfer,
+        )
+        .await?;
     }
 
-    let intent = (
-        token_in,       // token_in passed as parameter
-        amount_in,      // amount_in passed as parameter
-        src_user,       // src_user passed as parameter
-        token_out,      // token_out passed as parameter
-        amount_out,     // amount_out passed as parameter
-        dst_user,       // dst_user determined based on single/cross domain
-        "".to_string(), // winner_solver (currently empty, can be passed as needed)
-        timeout,        // timeout passed as parameter
-    );
+    ensure_escrow_allowance(
+        wallet,
+        contract_address,
+        token_in,
+        amount_in,
+        options.auto_approve,
+    )
+    .await?;
+    let call = contract.escrow_funds(info).value(value);
+    let pending_tx = call.send().await?;
+
+    let tx_receipt = pending_tx
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Failed to fetch transaction receipt"))?;
+
+    let intent_id = escrowed_intent_id(contract_address, &tx_receipt);
+
+    Ok((intent_id, tx_receipt))
+}
+
+/// Build the unsigned `escrowFunds` transaction of `intent` for `src_user`,
+/// with nonce, gas and fees filled in by `provider`, to be signed elsewhere.
+///
+/// The escrow contract must already be approved, as approving needs the key.
+pub async fn build_escrow_intent_ethereum(
+    provider: Arc<Provider<Http>>,
+    network: &Network,
+    chain_id: u64,
+    src_user: Address,
+    intent: &IntentRequest,
+    options: &EscrowOptions,
+) -> Result<(EscrowIntentInfo, TypedTransaction)> {
+    let token_in = intent.token_in.as_ethereum()?;
+    let amount_in = intent.amount_in.raw();
+    let contract_address = network.escrow_contract()?;
 
-    let value = if single_domain {
-        let mut value = U256::zero();
-        if token_in == H160::from_str("0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE").unwrap() {
-            value = amount_in;
+    if token_in != H160::from_str(NATIVE_TOKEN_ADDRESS)? {
+        let allowance = escrow_allowance(
+            provider.clone(),
+            contract_address,
+            src_user,
+            token_in,
+            amount_in,
+        )
+        .await?;
+        if allowance < amount_in {
+            anyhow::bail!(
+                "Escrow contract {:?} is only approved for {} of {:?} but the intent needs {}. \
+                 Run `approve {:?} {}` first",
+                contract_address,
+                allowance,
+                token_in,
+                amount_in,
+                token_in,
+                amount_in
+            );
         }
-        value
+        check_transfer_fee(
+            provider.as_ref(),
+            src_user,
+            contract_address,
+            token_in,
+            amount_in,
+            options.reject_fee_on_transfer,
+        )
+        .await?;
+    }
+
+    let contract = Escrow::new(contract_address, provider.clone());
+    let value = escrow_value(&contract, network, intent, options).await?;
+    let info = escrow_intent_info(src_user, intent)?;
+
+    let mut tx = contract
+        .escrow_funds(info.clone())
+        .value(value)
+        .from(src_user)
+        .tx;
+    tx.set_chain_id(chain_id);
+    provider
+        .fill_transaction(&mut tx, None)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fill the escrow transaction: {}", e))?;
+
+    Ok((info, tx))
+}
+
+/// `newIntentInfo` tuple taken by `escrowFunds`: token_in, amount_in, src_user,
+/// token_out, amount_out, dst_user, winner_solver and timeout.
+///
+/// The timeout is a duration in seconds, counted by the escrow contract from
+/// the block the intent is escrowed in.
+pub type EscrowIntentInfo = (Address, U256, Address, String, U256, String, String, U256);
+
+/// Escrow tuple of `intent` escrowed by `src_user`.
+pub fn escrow_intent_info(src_user: Address, intent: &IntentRequest) -> Result<EscrowIntentInfo> {
+    // Single domain intents pay out to the sender
+    let dst_user = if intent.is_single_domain() {
+        format!("0x{:x}", src_user)
     } else {
-        let mut value = U256::from_dec_str("12100000000000000").unwrap();
-        if token_in == H160::from_str("0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE").unwrap() {
-            value += amount_in;
-        }
-        value
+        intent
+            .dst_user
+            .map(|dst_user| dst_user.to_string())
+            .ok_or_else(|| anyhow::anyhow!("dst_user is required for cross-domain intents"))?
     };
 
-    // Call contract function with the constructed intent
-    let contract = contract.escrow_funds(intent).value(value);
+    Ok((
+        intent.token_in.as_ethereum()?,
+        intent.amount_in.raw(),
+        src_user,
+        intent.token_out.to_string(),
+        intent.amount_out.raw(),
+        dst_user,
+        intent.winner_solver.clone().unwrap_or_default(),
+        U256::from(intent.timeout),
+    ))
+}
+
+/// `newIntentInfo` passed in the call data of an `escrowFunds` transaction.
+pub fn decode_escrow_funds(data: &[u8]) -> Result<EscrowIntentInfo> {
+    let call = EscrowFundsCall::decode(data)
+        .map_err(|e| anyhow::anyhow!("Not an escrowFunds call: {}", e))?;
+    Ok(call.new_intent_info)
+}
+
+/// Timestamp of the block `block`, which the escrow contract compares
+/// timeouts with.
+async fn block_timestamp<M: Middleware>(client: &M, block: BlockNumber) -> Result<U256> {
+    let block = client
+        .get_block(block)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch block {:?}: {}", block, e))?
+        .ok_or_else(|| anyhow::anyhow!("Block {:?} not found", block))?;
+    Ok(block.timestamp)
+}
+
+/// Id of the intent escrowed by `receipt`, read from the `FundsEscrowed` log
+/// of the escrow contract, or `None` when the transaction stored no intent.
+pub fn escrowed_intent_id(contract_address: Address, receipt: &TransactionReceipt) -> Option<U256> {
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == contract_address)
+        .find_map(|log| {
+            let log = RawLog {
+                topics: log.topics.clone(),
+                data: log.data.to_vec(),
+            };
+            <FundsEscrowedFilter as EthEvent>::decode_log(&log).ok()
+        })
+        .map(|event| event.intent_id)
+}
+
+/// ETH sent with the escrow call: the cross-domain fee, checked against
+/// `max_bridge_fee`, plus `amount_in` when escrowing native ETH.
+async fn escrow_value<M: Middleware + 'static>(
+    contract: &Escrow<M>,
+    network: &Network,
+    intent: &IntentRequest,
+    options: &EscrowOptions,
+) -> Result<U256> {
+    let mut value = U256::zero();
+
+    if !intent.is_single_domain() {
+        let fee = cross_domain_fee(contract, network, options.bridge_fee).await?;
+        value += check_bridge_fee(fee, options.max_bridge_fee)?;
+    }
+
+    if intent.token_in.as_ethereum()? == H160::from_str(NATIVE_TOKEN_ADDRESS)? {
+        value += intent.amount_in.raw();
+    }
+    Ok(value)
+}
+
+/// Fee quoted by `crossDomainFee` of the escrow contract. Contracts without it
+/// charge `bridge_fee` when set, or else the default fee of `network`.
+async fn cross_domain_fee<M: Middleware + 'static>(
+    contract: &Escrow<M>,
+    network: &Network,
+    bridge_fee: Option<U256>,
+) -> Result<U256> {
+    let e = match contract.cross_domain_fee().call().await {
+        Ok(fee) => return Ok(fee),
+        Err(e) => e,
+    };
+    match (bridge_fee, network.cross_domain_fee) {
+        (Some(bridge_fee), _) => {
+            println!(
+                "Escrow contract does not quote a cross-domain fee ({}), paying --bridge-fee {} wei",
+                e, bridge_fee
+            );
+            Ok(bridge_fee)
+        }
+        (None, Some(default_fee)) => {
+            println!(
+                "Escrow contract does not quote a cross-domain fee ({}), paying the {} default of {} wei",
+                e, network.name, default_fee
+            );
+            Ok(default_fee)
+        }
+        (None, None) => anyhow::bail!(
+            "Failed to fetch the cross-domain fee from crossDomainFee of the escrow contract \
+             {:?}: {}. Pass the fee with --bridge-fee, the {} network has no default",
+            contract.address(),
+            e,
+            network.name
+        ),
+    }
+}
+
+/// `fee` unless it exceeds `max_bridge_fee`.
+fn check_bridge_fee(fee: U256, max_bridge_fee: Option<U256>) -> Result<U256> {
+    match max_bridge_fee {
+        Some(max_bridge_fee) if fee > max_bridge_fee => anyhow::bail!(
+            "Cross-domain fee {} wei exceeds --max-bridge-fee {} wei",
+            fee,
+            max_bridge_fee
+        ),
+        _ => Ok(fee),
+    }
+}
+
+/// Withdraw the funds of an intent from the escrow contract after its timeout.
+///
+/// The timeout counts from the block of the intent's `FundsEscrowed` log and
+/// is checked against the latest block timestamp before sending, so an early
+/// call fails here instead of reverting on chain.
+pub async fn user_cancel_intent_ethereum(
+    wallet: &Arc<EthereumSigner>,
+    contract_address: Address,
+    intent_id: U256,
+) -> Result<TransactionReceipt> {
+    let contract = Escrow::new(contract_address, wallet.clone());
+
+    let (_, _, src_user, _, _, _, _, timeout) = contract.intents(intent_id).call().await?;
+    if src_user == Address::zero() {
+        anyhow::bail!("Intent {} does not exist in the escrow contract", intent_id);
+    }
+    if src_user != wallet.address() {
+        anyhow::bail!(
+            "Intent {} belongs to {:?}, not {:?}",
+            intent_id,
+            src_user,
+            wallet.address()
+        );
+    }
+
+    let escrowed_at = escrow_block(&contract, intent_id).await?;
+    let escrowed_at = block_timestamp(wallet.as_ref(), escrowed_at.into()).await?;
+    let now = block_timestamp(wallet.as_ref(), BlockNumber::Latest).await?;
+    check_withdrawable(intent_id, escrowed_at.saturating_add(timeout), now)?;
+
+    let contract = contract.withdraw_funds(intent_id);
     let pending_tx = contract.send().await?;
 
     let tx_receipt = pending_tx
         .await?
-        .expect("Failed to fetch transaction receipt");
+        .ok_or_else(|| anyhow::anyhow!("Failed to fetch transaction receipt"))?;
 
     Ok(tx_receipt)
 }
 
-pub async fn _approve_erc20(
-    provider_url: &str,
-    private_key: &str,
-    token_address: &str,
-    spender_address: &str,
-    amount: &str,
-) -> Result<(), String> {
-    let provider = Provider::<Http>::try_from(provider_url)
-        .map_err(|e| format!("Failed to create provider: {}", e))?;
-    let provider = Arc::new(provider);
-
-    let wallet: LocalWallet = private_key
-        .parse()
-        .map_err(|e| format!("Failed to parse private key: {}", e))?;
-    let wallet = wallet.with_chain_id(1u64); // Mainnet
-    let wallet = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
-
-    let token_address = token_address
-        .parse::<Address>()
-        .map_err(|e| format!("Failed to parse token address: {}", e))?;
-    let erc20 = ERC20::new(token_address, wallet.clone());
-
-    let spender: Address = spender_address
-        .parse::<Address>()
-        .map_err(|e| format!("Failed to parse spender address: {}", e))?;
-    let amount =
-        U256::from_dec_str(amount).map_err(|e| format!("Failed to parse amount: {}", e))?;
-
-    let tx = erc20.approve(spender, amount);
+/// Number of the block that escrowed `intent_id`, from its `FundsEscrowed` log.
+async fn escrow_block<M: Middleware + 'static>(
+    contract: &Escrow<M>,
+    intent_id: U256,
+) -> Result<U64> {
+    let logs = contract
+        .funds_escrowed_filter()
+        .topic1(H256::from_uint(&intent_id))
+        .from_block(0u64)
+        .query_with_meta()
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to fetch the escrow log of intent {}: {}",
+                intent_id,
+                e
+            )
+        })?;
+    let (_, meta) = logs
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("No escrow log found for intent {}", intent_id))?;
+    Ok(meta.block_number)
+}
+
+/// Fail while the block timestamp `now` is before the intent's `deadline`.
+fn check_withdrawable(intent_id: U256, deadline: U256, now: U256) -> Result<()> {
+    if now < deadline {
+        anyhow::bail!(
+            "Intent {} cannot be withdrawn before its timeout, {} seconds left",
+            intent_id,
+            deadline - now
+        );
+    }
+    Ok(())
+}
+
+/// Fee in wei the escrow contract of `network` charges on top of `amount_in`
+/// for cross-domain intents, `bridge_fee` or the network's default when the
+/// contract does not quote one.
+pub async fn fetch_cross_domain_fee(
+    provider: Arc<Provider<Http>>,
+    network: &Network,
+    bridge_fee: Option<U256>,
+) -> Result<U256> {
+    let contract = Escrow::new(network.escrow_contract()?, provider);
+    cross_domain_fee(&contract, network, bridge_fee).await
+}
+
+/// Read `decimals()` of an ERC20 token, 18 for native ETH.
+pub async fn fetch_erc20_decimals(provider: Arc<Provider<Http>>, token: Address) -> Result<u8> {
+    if token == H160::from_str(NATIVE_TOKEN_ADDRESS)? {
+        return Ok(18);
+    }
+
+    let erc20 = ERC20::new(token, provider);
+    let decimals = erc20
+        .decimals()
+        .call()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch decimals of {:?}: {}", token, e))?;
+
+    Ok(decimals)
+}
+
+/// Allowance granted by [`approve_erc20`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalAmount {
+    Exact(U256),
+    Unlimited,
+    Revoke,
+}
+
+impl ApprovalAmount {
+    pub fn value(&self) -> U256 {
+        match self {
+            Self::Exact(amount) => *amount,
+            Self::Unlimited => U256::max_value(),
+            Self::Revoke => U256::zero(),
+        }
+    }
+}
+
+/// Approve `spender` to transfer `amount` of `token` on behalf of the signer.
+///
+/// Handles tokens that deviate from the ERC20 standard: tokens such as USDT
+/// that return nothing from `approve` are accepted, and tokens that refuse to
+/// change a non-zero allowance to another non-zero value get it reset to zero
+/// first.
+pub async fn approve_erc20(
+    wallet: &Arc<EthereumSigner>,
+    token: Address,
+    spender: Address,
+    amount: ApprovalAmount,
+) -> Result<TransactionReceipt> {
+    let erc20 = ERC20::new(token, wallet.clone());
+    let value = amount.value();
+
+    let current = erc20.allowance(wallet.address(), spender).call().await?;
+    if !current.is_zero() && !value.is_zero() && current != value {
+        if let Err(e) = simulate_approve(wallet, &erc20, spender, value).await {
+            println!(
+                "{:?} does not allow changing a non-zero allowance ({}), resetting it to zero first",
+                token, e
+            );
+            send_approve(wallet, &erc20, spender, U256::zero()).await?;
+        }
+    }
+
+    send_approve(wallet, &erc20, spender, value).await
+}
+
+/// Dry-run `approve` with `eth_call` and check its return value.
+///
+/// The raw return data is inspected instead of decoding it as a `bool`, so
+/// tokens whose `approve` returns nothing are accepted.
+async fn simulate_approve(
+    wallet: &Arc<EthereumSigner>,
+    erc20: &ERC20<EthereumSigner>,
+    spender: Address,
+    value: U256,
+) -> Result<()> {
+    let tx = erc20.approve(spender, value);
+    let output = wallet
+        .call(&tx.tx, None)
+        .await
+        .map_err(|e| anyhow::anyhow!("approve reverted: {}", e))?;
+
+    if output.is_empty() || output.iter().any(|byte| *byte != 0) {
+        Ok(())
+    } else {
+        anyhow::bail!("approve returned false")
+    }
+}
+
+async fn send_approve(
+    wallet: &Arc<EthereumSigner>,
+    erc20: &ERC20<EthereumSigner>,
+    spender: Address,
+    value: U256,
+) -> Result<TransactionReceipt> {
+    simulate_approve(wallet, erc20, spender, value).await?;
+
+    let tx = erc20.approve(spender, value);
     let pending_tx = tx
         .send()
         .await
-        .map_err(|e| format!("Failed to send transaction: {}", e))?;
+        .map_err(|e| anyhow::anyhow!("Failed to send transaction: {}", e))?;
 
-    pending_tx
+    let receipt = pending_tx
         .await
-        .map_err(|e| format!("Transaction failed: {}", e))?;
+        .map_err(|e| anyhow::anyhow!("Transaction failed: {}", e))?
+        .ok_or_else(|| anyhow::anyhow!("Failed to fetch transaction receipt"))?;
+    if receipt.status == Some(U64::zero()) {
+        anyhow::bail!("Approval reverted: {:?}", receipt.transaction_hash);
+    }
+
+    Ok(receipt)
+}
+
+/// Runtime code put at the sender's address by [`simulate_transfer`]. It
+/// calls `transfer(to, amount)` on `token` and returns how much `balanceOf(to)`
+/// grew, taking the ABI encoded `(token, to, amount)` as call data.
+const TRANSFER_PROBE_CODE: &str = "0x6370a0823160e01b600052602035600452602060006024600060006000355af11560815760005163a9059cbb60e01b600052602035600452604035602452600060006044600060006000355af1156081576370a0823160e01b600052602035600452602060006024600060006000355af1156081576000510360005260206000f35b600080fd";
+
+/// Amount of `token` that `to` receives when `from` transfers `amount` to it.
+///
+/// The transfer runs in an `eth_call` with the code of `from` overridden by
+/// [`TRANSFER_PROBE_CODE`], so nothing is sent. Nodes without state
+/// overrides in `eth_call` fail here.
+async fn simulate_transfer<M: Middleware>(
+    client: &M,
+    from: Address,
+    token: Address,
+    to: Address,
+    amount: U256,
+) -> Result<U256> {
+    let data = ethers::abi::encode(&[
+        ethers::abi::Token::Address(token),
+        ethers::abi::Token::Address(to),
+        ethers::abi::Token::Uint(amount),
+    ]);
+    let tx = json!({ "from": from, "to": from, "data": Bytes::from(data) });
+    let overrides = json!({ format!("{:?}", from): { "code": TRANSFER_PROBE_CODE } });
+
+    let output: Bytes = client
+        .provider()
+        .request("eth_call", (tx, "latest", overrides))
+        .await
+        .map_err(|e| anyhow::anyhow!("Transfer simulation failed: {}", e))?;
+    if output.len() != 32 {
+        anyhow::bail!("Unexpected transfer simulation output {}", output);
+    }
+
+    Ok(U256::from_big_endian(&output))
+}
+
+/// Warn when the escrow contract would receive less than `amount_in` of
+/// `token_in` from `src_user`, as happens with fee-on-transfer tokens. The
+/// intent then promises more than lands in escrow.
+///
+/// With `reject` set this fails instead. Only a warning is printed when the
+/// node cannot simulate the transfer.
+async fn check_transfer_fee<M: Middleware>(
+    client: &M,
+    src_user: Address,
+    escrow: Address,
+    token_in: Address,
+    amount_in: U256,
+    reject: bool,
+) -> Result<()> {
+    match simulate_transfer(client, src_user, token_in, escrow, amount_in).await {
+        Ok(received) if received < amount_in => {
+            let message = transfer_fee_warning(token_in, received, amount_in);
+            if reject {
+                anyhow::bail!("{}", message);
+            }
+            println!("Warning: {}", message);
+            Ok(())
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            println!(
+                "Could not check the amount received by the escrow contract: {}",
+                e
+            );
+            Ok(())
+        }
+    }
+}
+
+fn transfer_fee_warning(token_in: Address, received: U256, amount_in: U256) -> String {
+    format!(
+        "{:?} looks like a fee-on-transfer token: the escrow contract would receive {} \
+         instead of amount_in {}, {} less",
+        token_in,
+        received,
+        amount_in,
+        amount_in - received
+    )
+}
+
+/// Make sure the escrow contract may pull `amount_in` of `token_in` from the
+/// signer, approving exactly `amount_in` when `auto_approve` is set.
+pub async fn ensure_escrow_allowance(
+    wallet: &Arc<EthereumSigner>,
+    escrow: Address,
+    token_in: Address,
+    amount_in: U256,
+    auto_approve: bool,
+) -> Result<()> {
+    if token_in == H160::from_str(NATIVE_TOKEN_ADDRESS)? {
+        return Ok(());
+    }
+
+    let allowance = escrow_allowance(
+        wallet.clone(),
+        escrow,
+        wallet.address(),
+        token_in,
+        amount_in,
+    )
+    .await?;
+    if allowance >= amount_in {
+        return Ok(());
+    }
+
+    if !auto_approve {
+        anyhow::bail!(
+            "Escrow contract {:?} is only approved for {} of {:?} but the intent needs {}. \
+             Run `approve {:?} {}` first or pass --auto-approve",
+            escrow,
+            allowance,
+            token_in,
+            amount_in,
+            token_in,
+            amount_in
+        );
+    }
+
+    println!(
+        "Approving {} of {:?} to the escrow contract",
+        amount_in, token_in
+    );
+    let receipt = approve_erc20(wallet, token_in, escrow, ApprovalAmount::Exact(amount_in)).await?;
+    println!(
+        "Approval successful, receipt: {:?}",
+        receipt.transaction_hash
+    );
 
     Ok(())
 }
+
+/// Allowance of `escrow` for `token_in` held by `owner`, failing when `owner`
+/// holds less than `amount_in`.
+async fn escrow_allowance<M: Middleware + 'static>(
+    client: Arc<M>,
+    escrow: Address,
+    owner: Address,
+    token_in: Address,
+    amount_in: U256,
+) -> Result<U256> {
+    let erc20 = ERC20::new(token_in, client);
+
+    let balance = erc20.balance_of(owner).call().await?;
+    if balance < amount_in {
+        anyhow::bail!(
+            "Insufficient balance of {:?}: have {}, need {}",
+            token_in,
+            balance,
+            amount_in
+        );
+    }
+
+    Ok(erc20.allowance(owner, escrow).call().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intent::Domain;
+    use crate::network::{NetworkName, MAINNET_CROSS_DOMAIN_FEE};
+
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    fn escrowed_log(contract_address: Address, intent_id: u64, src_user: Address) -> Log {
+        Log {
+            address: contract_address,
+            topics: vec![
+                FundsEscrowedFilter::signature(),
+                H256::from_uint(&U256::from(intent_id)),
+                H256::from(src_user),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn escrow_timeout_is_a_duration() {
+        let src_user = Address::repeat_byte(1);
+        let intent = IntentRequest::builder(Domain::Ethereum, Domain::Ethereum)
+            .token_in(USDC)
+            .amount_in(100u64)
+            .token_out(USDC)
+            .amount_out(99u64)
+            .timeout(3600)
+            .build()
+            .unwrap();
+
+        let info = escrow_intent_info(src_user, &intent).unwrap();
+        assert_eq!(info.7, U256::from(3600));
+        assert_eq!(info.5, format!("0x{:x}", src_user));
+    }
+
+    #[test]
+    fn intent_id_is_read_from_the_escrow_log() {
+        let contract_address = ESCROW_CONTRACT_ADDRESS.parse().unwrap();
+        let src_user = Address::repeat_byte(1);
+        let receipt = TransactionReceipt {
+            logs: vec![
+                // A token transfer log of the same transaction.
+                Log {
+                    address: USDC.parse().unwrap(),
+                    topics: vec![H256::repeat_byte(7), H256::from(src_user)],
+                    data: Bytes::from(vec![0; 32]),
+                    ..Default::default()
+                },
+                escrowed_log(Address::repeat_byte(2), 5, src_user),
+                escrowed_log(contract_address, 42, src_user),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            escrowed_intent_id(contract_address, &receipt),
+            Some(U256::from(42))
+        );
+
+        let receipt = TransactionReceipt {
+            logs: receipt.logs[..2].to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(escrowed_intent_id(contract_address, &receipt), None);
+    }
+
+    #[test]
+    fn intents_are_withdrawable_from_their_deadline() {
+        let deadline = U256::from(1_700_003_600u64);
+        check_withdrawable(U256::one(), deadline, deadline).unwrap();
+        check_withdrawable(U256::one(), deadline, deadline + 1).unwrap();
+
+        let err = check_withdrawable(U256::one(), deadline, deadline - 60).unwrap_err();
+        assert!(err.to_string().contains("60 seconds left"), "{}", err);
+    }
+
+    #[test]
+    fn transfer_fee_warning_shows_the_difference() {
+        let warning = transfer_fee_warning(USDC.parse().unwrap(), 98u64.into(), 100u64.into());
+        assert!(
+            warning.contains("receive 98 instead of amount_in 100, 2 less"),
+            "{}",
+            warning
+        );
+    }
+
+    #[tokio::test]
+    async fn unquoted_cross_domain_fee_falls_back_to_the_network_default() {
+        let (provider, _mock) = Provider::mocked();
+        let contract = Escrow::new(Address::zero(), Arc::new(provider));
+        let mainnet = Network::new(NetworkName::Mainnet);
+
+        let fee = cross_domain_fee(&contract, &mainnet, None).await.unwrap();
+        assert_eq!(fee, U256::from(MAINNET_CROSS_DOMAIN_FEE));
+        let fee = cross_domain_fee(&contract, &mainnet, Some(U256::one()))
+            .await
+            .unwrap();
+        assert_eq!(fee, U256::one());
+
+        let localnet = Network::new(NetworkName::Localnet);
+        let err = cross_domain_fee(&contract, &localnet, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--bridge-fee"), "{}", err);
+    }
+
+    #[test]
+    fn bridge_fee_within_cap() {
+        let fee = U256::from(12_100_000_000_000_000u64);
+        assert_eq!(check_bridge_fee(fee, None).unwrap(), fee);
+        assert_eq!(check_bridge_fee(fee, Some(fee)).unwrap(), fee);
+        assert_eq!(check_bridge_fee(fee, Some(fee * 2)).unwrap(), fee);
+    }
+
+    #[test]
+    fn bridge_fee_above_cap() {
+        let fee = U256::from(12_100_000_000_000_000u64);
+        let err = check_bridge_fee(fee, Some(fee - 1)).unwrap_err();
+        assert!(err.to_string().contains("exceeds --max-bridge-fee"));
+        assert!(check_bridge_fee(U256::one(), Some(U256::zero())).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::address::DomainAddress;
+use crate::amount::TokenAmount;
+use crate::solana::TxSendMethod;
+
+/// Chain an intent escrows funds on or pays out to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Domain {
+    Solana,
+    Ethereum,
+}
+
+/// A user intent, independent of how it is escrowed.
+///
+/// The same value drives the CLI, [`crate::MantisClient`] and saved intent
+/// files. Build it with [`IntentRequest::builder`] or deserialize it with
+/// [`IntentRequest::load`]; both validate the request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntentRequest {
+    pub src_domain: Domain,
+    pub dst_domain: Domain,
+    pub token_in: DomainAddress,
+    pub amount_in: TokenAmount,
+    pub token_out: DomainAddress,
+    pub amount_out: TokenAmount,
+    /// Timeout duration in seconds.
+    pub timeout: u64,
+    /// Recipient on the destination domain, required for cross-domain intents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dst_user: Option<DomainAddress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner_solver: Option<String>,
+}
+
+impl IntentRequest {
+    pub fn builder(src_domain: Domain, dst_domain: Domain) -> IntentRequestBuilder {
+        IntentRequestBuilder {
+            src_domain,
+            dst_domain,
+            token_in: None,
+            amount_in: None,
+            token_out: None,
+            amount_out: None,
+            timeout: None,
+            dst_user: None,
+            winner_solver: None,
+        }
+    }
+
+    pub fn is_single_domain(&self) -> bool {
+        self.src_domain == self.dst_domain
+    }
+
+    /// Check that the request can be escrowed on its source domain and paid
+    /// out on its destination domain.
+    pub fn validate(&self) -> Result<()> {
+        check_domain("token_in", &self.token_in, self.src_domain)?;
+        check_domain("token_out", &self.token_out, self.dst_domain)?;
+        if let Some(dst_user) = &self.dst_user {
+            check_domain("dst_user", dst_user, self.dst_domain)?;
+        }
+
+        if self.src_domain == Domain::Solana && self.winner_solver.is_some() {
+            bail!("winner_solver is only supported for intents escrowed on Ethereum");
+        }
+        if self.amount_in.is_zero() {
+            bail!("amount_in must be greater than zero");
+        }
+        if self.src_domain == Domain::Solana {
+            self.amount_in.to_u64()?;
+        }
+        if self.amount_out.is_zero() {
+            bail!("amount_out must be greater than zero");
+        }
+        if self.timeout == 0 {
+            bail!("timeout must be greater than zero");
+        }
+
+        match (self.is_single_domain(), &self.dst_user) {
+            (true, Some(_)) => bail!("dst_user is always the sender for single domain intents"),
+            (false, None) => bail!("dst_user is required for cross-domain intents"),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let intent: Self = serde_json::from_str(json)?;
+        intent.validate()?;
+        Ok(intent)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Read and validate an intent file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read intent file {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    /// Write the intent as pretty-printed JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_json()?)
+            .map_err(|e| anyhow!("Failed to write intent file {}: {}", path.display(), e))
+    }
+}
+
+/// Options for escrowing an intent that are not part of the intent itself.
+#[derive(Debug, Clone, Default)]
+pub struct EscrowOptions {
+    /// How Solana transactions are sent.
+    pub tx_send_method: TxSendMethod,
+    /// Approve the Ethereum escrow contract for `amount_in` when the ERC20
+    /// allowance is too low, instead of failing.
+    pub auto_approve: bool,
+    /// Refuse to escrow tokens that lose part of `amount_in` on transfer,
+    /// instead of warning about them.
+    pub reject_fee_on_transfer: bool,
+    /// Abort Ethereum cross-domain escrows when the fee quoted by the escrow
+    /// contract, in wei, is above this cap.
+    pub max_bridge_fee: Option<U256>,
+    /// Cross-domain fee in wei paid when the escrow contract does not quote
+    /// one with `crossDomainFee`.
+    pub bridge_fee: Option<U256>,
+}
+
+/// Builder for [`IntentRequest`], see [`IntentRequest::builder`].
+#[derive(Debug, Clone)]
+pub struct IntentRequestBuilder {
+    src_domain: Domain,
+    dst_domain: Domain,
+    token_in: Option<String>,
+    amount_in: Option<TokenAmount>,
+    token_out: Option<String>,
+    amount_out: Option<TokenAmount>,
+    timeout: Option<u64>,
+    dst_user: Option<String>,
+    winner_solver: Option<String>,
+}
+
+impl IntentRequestBuilder {
+    pub fn token_in(mut self, token_in: impl Into<String>) -> Self {
+        self.token_in = Some(token_in.into());
+        self
+    }
+
+    pub fn amount_in(mut self, amount_in: impl Into<TokenAmount>) -> Self {
+        self.amount_in = Some(amount_in.into());
+        self
+    }
+
+    pub fn token_out(mut self, token_out: impl Into<String>) -> Self {
+        self.token_out = Some(token_out.into());
+        self
+    }
+
+    pub fn amount_out(mut self, amount_out: impl Into<TokenAmount>) -> Self {
+        self.amount_out = Some(amount_out.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: u64) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn dst_user(mut self, dst_user: impl Into<String>) -> Self {
+        self.dst_user = Some(dst_user.into());
+        self
+    }
+
+    pub fn winner_solver(mut self, winner_solver: impl Into<String>) -> Self {
+        self.winner_solver = Some(winner_solver.into());
+        self
+    }
+
+    pub fn build(self) -> Result<IntentRequest> {
+        let token_in = self
+            .token_in
+            .ok_or_else(|| anyhow!("token_in is required"))?;
+        let token_out = self
+            .token_out
+            .ok_or_else(|| anyhow!("token_out is required"))?;
+        let dst_user = self
+            .dst_user
+            .map(|dst_user| DomainAddress::parse(self.dst_domain, &dst_user))
+            .transpose()
+            .map_err(|e| {
+                anyhow!(
+                    "Invalid dst_user for {} destination: {}",
+                    self.dst_domain,
+                    e
+                )
+            })?;
+
+        let intent = IntentRequest {
+            src_domain: self.src_domain,
+            dst_domain: self.dst_domain,
+            token_in: DomainAddress::parse(self.src_domain, &token_in)
+                .map_err(|e| anyhow!("Invalid token_in for {} source: {}", self.src_domain, e))?,
+            amount_in: self
+                .amount_in
+                .ok_or_else(|| anyhow!("amount_in is required"))?,
+            token_out: DomainAddress::parse(self.dst_domain, &token_out).map_err(|e| {
+                anyhow!(
+                    "Invalid token_out for {} destination: {}",
+                    self.dst_domain,
+                    e
+                )
+            })?,
+            amount_out: self
+                .amount_out
+                .ok_or_else(|| anyhow!("amount_out is required"))?,
+            timeout: self.timeout.ok_or_else(|| anyhow!("timeout is required"))?,
+            dst_user,
+            winner_solver: self.winner_solver,
+        };
+        intent.validate()?;
+        Ok(intent)
+    }
+}
+
+fn check_domain(field: &str, address: &DomainAddress, domain: Domain) -> Result<()> {
+    if address.domain() != domain {
+        bail!(
+            "{} {} is a {} address but the intent expects a {} address",
+            field,
+            address,
+            address.domain(),
+            domain
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WSOL: &str = "So11111111111111111111111111111111111111112";
+    const USDC_SOLANA: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const USDC_ETHEREUM: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    const ETHEREUM_USER: &str = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e";
+
+    fn solana_to_ethereum() -> IntentRequest {
+        IntentRequest::builder(Domain::Solana, Domain::Ethereum)
+            .token_in(WSOL)
+            .amount_in(50u64)
+            .token_out(USDC_ETHEREUM)
+            .amount_out(49u64)
+            .timeout(3600)
+            .dst_user(ETHEREUM_USER)
+            .build()
+            .unwrap()
+    }
+
+    fn assert_invalid(intent: IntentRequest, expected: &str) {
+        let err = intent.validate().unwrap_err();
+        assert!(err.to_string().contains(expected), "{}", err);
+    }
+
+    #[test]
+    fn valid_intents() {
+        solana_to_ethereum().validate().unwrap();
+        IntentRequest::builder(Domain::Solana, Domain::Solana)
+            .token_in(WSOL)
+            .amount_in(1u64)
+            .token_out(USDC_SOLANA)
+            .amount_out(1u64)
+            .timeout(60)
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn addresses_must_match_their_domain() {
+        let mut intent = solana_to_ethereum();
+        intent.token_out = DomainAddress::parse(Domain::Solana, USDC_SOLANA).unwrap();
+        assert_invalid(intent, "token_out");
+
+        let mut intent = solana_to_ethereum();
+        intent.token_in = DomainAddress::parse(Domain::Ethereum, USDC_ETHEREUM).unwrap();
+        assert_invalid(intent, "token_in");
+
+        let mut intent = solana_to_ethereum();
+        intent.dst_user = Some(DomainAddress::parse(Domain::Solana, WSOL).unwrap());
+        assert_invalid(intent, "dst_user");
+    }
+
+    #[test]
+    fn amounts_and_timeout_must_be_positive() {
+        let mut intent = solana_to_ethereum();
+        intent.amount_in = TokenAmount::from(0u64);
+        assert_invalid(intent, "amount_in must be greater than zero");
+
+        let mut intent = solana_to_ethereum();
+        intent.amount_out = TokenAmount::from(0u64);
+        assert_invalid(intent, "amount_out must be greater than zero");
+
+        let mut intent = solana_to_ethereum();
+        intent.timeout = 0;
+        assert_invalid(intent, "timeout must be greater than zero");
+    }
+
+    #[test]
+    fn solana_amount_in_must_fit_in_a_u64() {
+        let mut intent = solana_to_ethereum();
+        intent.amount_in = TokenAmount::from(U256::from(u64::MAX) + 1);
+        assert_invalid(intent, "does not fit in a u64");
+
+        let mut intent = solana_to_ethereum();
+        intent.amount_out = TokenAmount::from(U256::from(u64::MAX) + 1);
+        intent.validate().unwrap();
+    }
+
+    #[test]
+    fn dst_user_depends_on_the_domains() {
+        let mut intent = solana_to_ethereum();
+        intent.dst_user = None;
+        assert_invalid(intent, "dst_user is required");
+
+        let mut intent = solana_to_ethereum();
+        intent.dst_domain = Domain::Solana;
+        intent.token_out = DomainAddress::parse(Domain::Solana, USDC_SOLANA).unwrap();
+        intent.dst_user = Some(DomainAddress::parse(Domain::Solana, WSOL).unwrap());
+        assert_invalid(intent, "dst_user is always the sender");
+    }
+
+    #[test]
+    fn winner_solver_needs_an_ethereum_escrow() {
+        let mut intent = solana_to_ethereum();
+        intent.winner_solver = Some(ETHEREUM_USER.to_string());
+        assert_invalid(intent, "winner_solver");
+    }
+
+    #[test]
+    fn intent_files_are_validated() {
+        let intent = solana_to_ethereum();
+        assert_eq!(
+            IntentRequest::from_json(&intent.to_json().unwrap()).unwrap(),
+            intent
+        );
+
+        let mut invalid = intent;
+        invalid.timeout = 0;
+        let json = serde_json::to_string(&invalid).unwrap();
+        assert!(IntentRequest::from_json(&json).is_err());
+    }
+}
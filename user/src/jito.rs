@@ -0,0 +1,765 @@
+//! Jito block engine JSON-RPC API, which unlike the searcher gRPC API needs
+//! no whitelisted key.
+//!
+//! Bundles are sent with `sendBundle` to `{block_engine_url}/api/v1/bundles`
+//! and followed with `getInflightBundleStatuses` until they land, then with
+//! `getBundleStatuses` until they are confirmed.
+//!
+//! The tips of recently landed bundles are read from the public tip floor
+//! feed, and bundles are simulated with `simulateBundle` on the Solana RPC
+//! node when it supports it.
+//!
+//! [`JitoBlockEngines`] sends each bundle to several block engine regions at
+//! once, over either API, keeping one authenticated searcher connection per
+//! region.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures::future::select_ok;
+use jito_protos::bundle::BundleResult;
+use jito_protos::searcher::searcher_service_client::SearcherServiceClient;
+use jito_protos::searcher::SubscribeBundleResultsRequest;
+use jito_searcher_client::token_authenticator::ClientInterceptor;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::native_token::sol_to_lamports;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use tokio::time::sleep;
+use tonic::codegen::InterceptedService;
+use tonic::transport::Channel;
+use tonic::Streaming;
+
+use crate::solana::SimulationFailure;
+
+/// Path of the bundle API below the block engine URL.
+pub const BUNDLES_PATH: &str = "/api/v1/bundles";
+
+/// Tip floor feed of mainnet bundles.
+pub const JITO_TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
+
+/// Percentiles published by the tip floor feed.
+pub const TIP_FLOOR_PERCENTILES: [u8; 5] = [25, 50, 75, 95, 99];
+
+/// Mainnet block engine regions, by name.
+pub const JITO_REGIONS: [(&str, &str); 6] = [
+    ("mainnet", "https://mainnet.block-engine.jito.wtf"),
+    (
+        "amsterdam",
+        "https://amsterdam.mainnet.block-engine.jito.wtf",
+    ),
+    (
+        "frankfurt",
+        "https://frankfurt.mainnet.block-engine.jito.wtf",
+    ),
+    ("ny", "https://ny.mainnet.block-engine.jito.wtf"),
+    ("slc", "https://slc.mainnet.block-engine.jito.wtf"),
+    ("tokyo", "https://tokyo.mainnet.block-engine.jito.wtf"),
+];
+
+/// JSON-RPC error code of unknown methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Status polls before a bundle is considered dropped, one per second.
+const CONFIRMATION_POLLS: u32 = 30;
+
+/// Polls a bundle may be reported `Invalid` before it is considered
+/// dropped, as the block engine may not know it right after it is sent.
+const INVALID_POLLS: u32 = 5;
+
+/// Client of the block engine JSON-RPC API.
+#[derive(Debug, Clone)]
+pub struct JitoHttpClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+/// Entry of `getInflightBundleStatuses`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InflightBundleStatus {
+    pub bundle_id: String,
+    /// `Invalid`, `Pending`, `Failed` or `Landed`.
+    pub status: String,
+    pub landed_slot: Option<u64>,
+}
+
+/// Entry of `getBundleStatuses`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BundleStatus {
+    pub bundle_id: String,
+    pub transactions: Vec<String>,
+    pub slot: u64,
+    /// `processed`, `confirmed` or `finalized`.
+    pub confirmation_status: Option<String>,
+    /// `{"Ok": null}` when the bundle succeeded.
+    pub err: Value,
+}
+
+/// Tips of recently landed bundles, in SOL.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TipFloor {
+    pub landed_tips_25th_percentile: f64,
+    pub landed_tips_50th_percentile: f64,
+    pub landed_tips_75th_percentile: f64,
+    pub landed_tips_95th_percentile: f64,
+    pub landed_tips_99th_percentile: f64,
+}
+
+impl TipFloor {
+    /// Tip at `percentile`, one of [`TIP_FLOOR_PERCENTILES`], in lamports.
+    /// Other percentiles get the next published one above them.
+    pub fn percentile(&self, percentile: u8) -> u64 {
+        let tip = match percentile {
+            0..=25 => self.landed_tips_25th_percentile,
+            26..=50 => self.landed_tips_50th_percentile,
+            51..=75 => self.landed_tips_75th_percentile,
+            76..=95 => self.landed_tips_95th_percentile,
+            _ => self.landed_tips_99th_percentile,
+        };
+        sol_to_lamports(tip)
+    }
+}
+
+/// Read the latest entry of the tip floor feed at `url`.
+pub async fn fetch_tip_floor(url: &str) -> Result<TipFloor> {
+    reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| anyhow!("Tip floor {}: {}", url, e))?
+        .json::<Vec<TipFloor>>()
+        .await
+        .map_err(|e| anyhow!("Tip floor {} sent an invalid response: {}", url, e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Tip floor {} is empty", url))
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct StatusesResult<T> {
+    value: Vec<Option<T>>,
+}
+
+#[derive(Deserialize)]
+struct SimulateBundleResult {
+    value: SimulatedBundle,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulatedBundle {
+    /// `"succeeded"` or `{"failed": {"error": .., "tx_signature": ..}}`.
+    summary: Value,
+    #[serde(default)]
+    transaction_results: Vec<SimulatedTransaction>,
+}
+
+#[derive(Deserialize)]
+struct SimulatedTransaction {
+    err: Option<Value>,
+    logs: Option<Vec<String>>,
+}
+
+impl JitoHttpClient {
+    pub fn new(block_engine_url: &str) -> Self {
+        Self {
+            url: format!("{}{}", block_engine_url.trim_end_matches('/'), BUNDLES_PATH),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send signed `transactions` as a bundle, returning its id.
+    pub async fn send_bundle(&self, transactions: &[Transaction]) -> Result<String> {
+        let encoded = encode_transactions(transactions)?;
+        self.call("sendBundle", json!([encoded, { "encoding": "base64" }]))
+            .await
+    }
+
+    pub async fn get_inflight_bundle_statuses(
+        &self,
+        bundle_ids: &[String],
+    ) -> Result<Vec<Option<InflightBundleStatus>>> {
+        let result: StatusesResult<InflightBundleStatus> = self
+            .call("getInflightBundleStatuses", json!([bundle_ids]))
+            .await?;
+        Ok(result.value)
+    }
+
+    pub async fn get_bundle_statuses(
+        &self,
+        bundle_ids: &[String],
+    ) -> Result<Vec<Option<BundleStatus>>> {
+        let result: StatusesResult<BundleStatus> =
+            self.call("getBundleStatuses", json!([bundle_ids])).await?;
+        Ok(result.value)
+    }
+
+    /// Send `transactions` as a bundle and wait until it is confirmed,
+    /// returning the signatures of its transactions.
+    pub async fn send_bundle_with_confirmation(
+        &self,
+        transactions: &[Transaction],
+    ) -> Result<Vec<Signature>> {
+        let bundle_id = self.send_bundle(transactions).await?;
+        println!("Sent bundle {}", bundle_id);
+        let bundle_ids = [bundle_id.clone()];
+        let mut invalid_polls = 0;
+
+        for _ in 0..CONFIRMATION_POLLS {
+            sleep(Duration::from_secs(1)).await;
+
+            // Bundles that have not landed are `Pending`, or `Invalid` while
+            // the block engine does not know them.
+            if let Some(Some(inflight)) = self
+                .get_inflight_bundle_statuses(&bundle_ids)
+                .await?
+                .first()
+            {
+                match inflight.status.as_str() {
+                    "Failed" => bail!("Bundle {} failed", bundle_id),
+                    "Invalid" => {
+                        invalid_polls += 1;
+                        if invalid_polls >= INVALID_POLLS {
+                            bail!(
+                                "Bundle {} still invalid after {} polls",
+                                bundle_id,
+                                invalid_polls
+                            );
+                        }
+                        continue;
+                    }
+                    "Landed" => {}
+                    _ => continue,
+                }
+            }
+
+            let Some(Some(status)) = self
+                .get_bundle_statuses(&bundle_ids)
+                .await?
+                .first()
+                .cloned()
+            else {
+                continue;
+            };
+            if !status.err.is_null() && status.err != json!({ "Ok": null }) {
+                bail!("Bundle {} failed: {}", bundle_id, status.err);
+            }
+            if matches!(
+                status.confirmation_status.as_deref(),
+                Some("confirmed") | Some("finalized")
+            ) {
+                return status
+                    .transactions
+                    .iter()
+                    .map(|signature| {
+                        signature
+                            .parse()
+                            .map_err(|e| anyhow!("Invalid signature {}: {}", signature, e))
+                    })
+                    .collect();
+            }
+        }
+        bail!("Bundle {} was not confirmed", bundle_id)
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let response = json_rpc(&self.client, &self.url, method, params).await?;
+        match (response.result, response.error) {
+            (_, Some(error)) => bail!("{} failed: {}", method, error),
+            (Some(result), None) => Ok(result),
+            (None, None) => bail!("{} returned no result", method),
+        }
+    }
+}
+
+/// Simulate `transactions` as a bundle, returning the first failure.
+///
+/// Jito's RPC nodes simulate the whole bundle with `simulateBundle`. Other
+/// nodes simulate each transaction with `simulateTransaction`, without the
+/// changes made by the previous ones. Errors are only for failing to
+/// simulate.
+pub async fn simulate_bundle(
+    rpc_client: &RpcClient,
+    transactions: &[Transaction],
+) -> Result<Option<SimulationFailure>> {
+    let no_accounts = vec![Value::Null; transactions.len()];
+    let params = json!([
+        { "encodedTransactions": encode_transactions(transactions)? },
+        {
+            "preExecutionAccountsConfigs": no_accounts,
+            "postExecutionAccountsConfigs": no_accounts,
+            "skipSigVerify": true,
+            "replaceRecentBlockhash": true,
+        },
+    ]);
+    let response: RpcResponse<SimulateBundleResult> = json_rpc(
+        &reqwest::Client::new(),
+        &rpc_client.url(),
+        "simulateBundle",
+        params,
+    )
+    .await?;
+
+    let bundle = match (response.result, response.error) {
+        (Some(result), _) => result.value,
+        (None, Some(error)) if error["code"].as_i64() == Some(METHOD_NOT_FOUND) => {
+            return simulate_transactions(rpc_client, transactions).await
+        }
+        (None, error) => bail!("simulateBundle failed: {}", error.unwrap_or_default()),
+    };
+    if bundle.summary == "succeeded" {
+        return Ok(None);
+    }
+
+    let failed = bundle
+        .transaction_results
+        .into_iter()
+        .zip(transactions)
+        .find(|(result, _)| result.err.as_ref().is_some_and(|err| !err.is_null()));
+    Ok(Some(match failed {
+        Some((result, transaction)) => {
+            let error = result.err.unwrap_or_default();
+            let logs = result.logs.unwrap_or_default();
+            match serde_json::from_value(error.clone()) {
+                Ok(transaction_error) => {
+                    SimulationFailure::new(transaction, transaction_error, logs)
+                }
+                Err(_) => SimulationFailure {
+                    error: error.to_string(),
+                    transaction_error: None,
+                    program_id: None,
+                    logs,
+                },
+            }
+        }
+        None => SimulationFailure {
+            error: bundle.summary["failed"]["error"].to_string(),
+            transaction_error: None,
+            program_id: None,
+            logs: Vec::new(),
+        },
+    }))
+}
+
+async fn simulate_transactions(
+    rpc_client: &RpcClient,
+    transactions: &[Transaction],
+) -> Result<Option<SimulationFailure>> {
+    for transaction in transactions {
+        let result = rpc_client
+            .simulate_transaction_with_config(
+                transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(rpc_client.commitment()),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .value;
+        if let Some(err) = result.err {
+            return Ok(Some(SimulationFailure::new(
+                transaction,
+                err,
+                result.logs.unwrap_or_default(),
+            )));
+        }
+    }
+    Ok(None)
+}
+
+fn encode_transactions(transactions: &[Transaction]) -> Result<Vec<String>> {
+    transactions
+        .iter()
+        .map(|transaction| Ok(BASE64.encode(bincode::serialize(transaction)?)))
+        .collect()
+}
+
+async fn json_rpc<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    method: &str,
+    params: Value,
+) -> Result<RpcResponse<T>> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    client
+        .post(url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| anyhow!("{}: {}", url, e))?
+        .json()
+        .await
+        .map_err(|e| anyhow!("{} sent an invalid response: {}", url, e))
+}
+
+type SearcherClient = SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>;
+
+/// Block engine URLs of comma separated region names or URLs, `all` being
+/// every region of [`JITO_REGIONS`] but the load balanced `mainnet`.
+pub fn resolve_jito_regions(regions: &str) -> Result<Vec<String>> {
+    let mut urls = Vec::new();
+    for region in regions
+        .split(',')
+        .map(str::trim)
+        .filter(|region| !region.is_empty())
+    {
+        if region == "all" {
+            urls.extend(JITO_REGIONS[1..].iter().map(|(_, url)| url.to_string()));
+        } else if region.contains("://") {
+            urls.push(region.to_string());
+        } else {
+            let (_, url) = JITO_REGIONS
+                .iter()
+                .find(|(name, _)| *name == region)
+                .ok_or_else(|| anyhow!("Unknown Jito region {}", region))?;
+            urls.push(url.to_string());
+        }
+    }
+    let mut unique = Vec::new();
+    for url in urls {
+        if !unique.contains(&url) {
+            unique.push(url);
+        }
+    }
+    Ok(unique)
+}
+
+/// Block engine regions every bundle is raced through.
+///
+/// Clones share their connections and statistics, so a [`JitoBlockEngines`]
+/// can be kept in a [`crate::solana::SubmitConfig`] for the lifetime of a
+/// client.
+#[derive(Clone, Default)]
+pub struct JitoBlockEngines {
+    regions: Arc<Vec<Region>>,
+    /// Regions raced per bundle, the ones with the lowest latency. All of
+    /// them when `None`.
+    pub race: Option<usize>,
+}
+
+struct Region {
+    url: String,
+    http: JitoHttpClient,
+    /// Searcher connection, made on first use and after failed or cancelled
+    /// bundles.
+    searcher: tokio::sync::Mutex<Option<SearcherConnection>>,
+    stats: Mutex<RegionStats>,
+}
+
+struct SearcherConnection {
+    auth: Pubkey,
+    client: SearcherClient,
+    bundle_results: Streaming<BundleResult>,
+}
+
+/// Latency and outcomes of the bundles sent through one region.
+#[derive(Debug, Clone, Default)]
+pub struct RegionStats {
+    pub url: String,
+    /// Time taken to connect and authenticate, last time it happened.
+    pub connect_latency: Option<Duration>,
+    pub sent: u32,
+    /// Bundles confirmed through this region first.
+    pub landed: u32,
+    pub failed: u32,
+    /// Bundles dropped unfinished because another region confirmed them
+    /// first.
+    pub cancelled: u32,
+    /// Time from sending to confirmation, summed over landed bundles.
+    pub total_latency: Duration,
+}
+
+impl RegionStats {
+    pub fn average_latency(&self) -> Option<Duration> {
+        (self.landed > 0).then(|| self.total_latency / self.landed)
+    }
+
+    /// Latency regions are ranked by. Regions never measured come first, so
+    /// they get measured.
+    fn ranking_latency(&self) -> Duration {
+        self.average_latency()
+            .or(self.connect_latency)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for JitoBlockEngines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JitoBlockEngines")
+            .field("urls", &self.urls())
+            .field("race", &self.race)
+            .finish()
+    }
+}
+
+impl JitoBlockEngines {
+    pub fn new(urls: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let regions = urls
+            .into_iter()
+            .map(|url| {
+                let url = url.into();
+                Region {
+                    http: JitoHttpClient::new(&url),
+                    searcher: tokio::sync::Mutex::new(None),
+                    stats: Mutex::new(RegionStats {
+                        url: url.clone(),
+                        ..Default::default()
+                    }),
+                    url,
+                }
+            })
+            .collect();
+        Self {
+            regions: Arc::new(regions),
+            race: None,
+        }
+    }
+
+    /// Race `race` regions per bundle instead of all of them.
+    pub fn with_race(mut self, race: usize) -> Self {
+        self.race = Some(race);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn urls(&self) -> Vec<&str> {
+        self.regions
+            .iter()
+            .map(|region| region.url.as_str())
+            .collect()
+    }
+
+    pub fn stats(&self) -> Vec<RegionStats> {
+        self.regions
+            .iter()
+            .map(|region| region.stats().clone())
+            .collect()
+    }
+
+    /// Send `transactions` as a bundle through the searcher gRPC API of the
+    /// raced regions, returning the signatures of the first confirmation.
+    ///
+    /// Connections are authenticated with `auth_keypair`.
+    pub async fn send_bundle(
+        &self,
+        rpc_client: &RpcClient,
+        auth_keypair: &Arc<Keypair>,
+        transactions: &[Transaction],
+    ) -> Result<Vec<Signature>> {
+        let races = self
+            .raced_regions()?
+            .into_iter()
+            .map(|region| Box::pin(region.send_bundle(rpc_client, auth_keypair, transactions)));
+        Ok(select_ok(races).await?.0)
+    }
+
+    /// Send `transactions` as a bundle through the JSON-RPC API of the raced
+    /// regions, returning the signatures of the first confirmation.
+    pub async fn send_bundle_http(&self, transactions: &[Transaction]) -> Result<Vec<Signature>> {
+        let races = self.raced_regions()?.into_iter().map(|region| {
+            Box::pin(region.timed(region.http.send_bundle_with_confirmation(transactions)))
+        });
+        Ok(select_ok(races).await?.0)
+    }
+
+    /// The `race` regions with the lowest latency.
+    fn raced_regions(&self) -> Result<Vec<&Region>> {
+        if self.regions.is_empty() {
+            bail!("No Jito block engine configured");
+        }
+        let mut regions: Vec<&Region> = self.regions.iter().collect();
+        regions.sort_by_key(|region| region.stats().ranking_latency());
+        regions.truncate(self.race.unwrap_or(regions.len()).max(1));
+        Ok(regions)
+    }
+}
+
+impl Region {
+    fn stats(&self) -> std::sync::MutexGuard<'_, RegionStats> {
+        self.stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Run `send`, recording its outcome and latency. When the race drops it
+    /// first, it is recorded as cancelled.
+    async fn timed(
+        &self,
+        send: impl std::future::Future<Output = Result<Vec<Signature>>>,
+    ) -> Result<Vec<Signature>> {
+        self.stats().sent += 1;
+        let mut attempt = Attempt {
+            region: self,
+            finished: false,
+        };
+        let start = Instant::now();
+        let result = send.await.map_err(|e| anyhow!("{}: {}", self.url, e));
+        attempt.finished = true;
+
+        let mut stats = self.stats();
+        match &result {
+            Ok(_) => {
+                stats.landed += 1;
+                stats.total_latency += start.elapsed();
+            }
+            Err(_) => stats.failed += 1,
+        }
+        result
+    }
+
+    async fn send_bundle(
+        &self,
+        rpc_client: &RpcClient,
+        auth_keypair: &Arc<Keypair>,
+        transactions: &[Transaction],
+    ) -> Result<Vec<Signature>> {
+        let mut searcher = self.searcher.lock().await;
+        // Taken out while in use, so a bundle that fails or loses the race
+        // drops the connection with its half-read bundle results.
+        let mut connection = match searcher.take() {
+            Some(connection) if connection.auth == auth_keypair.pubkey() => connection,
+            _ => self.connect(auth_keypair).await?,
+        };
+
+        let transactions: Vec<VersionedTransaction> =
+            transactions.iter().cloned().map(Into::into).collect();
+        let result = self
+            .timed(async {
+                jito_searcher_client::send_bundle_with_confirmation(
+                    &transactions,
+                    rpc_client,
+                    &mut connection.client,
+                    &mut connection.bundle_results,
+                )
+                .await
+                .map_err(|e| anyhow!("Failed to send bundle: {}", e))
+            })
+            .await;
+        if result.is_ok() {
+            *searcher = Some(connection);
+        }
+        result
+    }
+
+    /// Connect to the searcher API, authenticated with `auth_keypair`.
+    async fn connect(&self, auth_keypair: &Arc<Keypair>) -> Result<SearcherConnection> {
+        let start = Instant::now();
+        let mut client = jito_searcher_client::get_searcher_client(&self.url, auth_keypair)
+            .await
+            .map_err(|e| anyhow!("Failed to connect to {}: {}", self.url, e))?;
+        let bundle_results = client
+            .subscribe_bundle_results(SubscribeBundleResultsRequest {})
+            .await?
+            .into_inner();
+        self.stats().connect_latency = Some(start.elapsed());
+        Ok(SearcherConnection {
+            auth: auth_keypair.pubkey(),
+            client,
+            bundle_results,
+        })
+    }
+}
+
+/// Bundle sent through a region, counted as cancelled when it is dropped
+/// before it finishes.
+struct Attempt<'a> {
+    region: &'a Region,
+    finished: bool,
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.region.stats().cancelled += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::{self, FutureExt};
+
+    #[tokio::test]
+    async fn race_losers_are_counted_as_cancelled() {
+        let engines = JitoBlockEngines::new(["http://winner", "http://loser"]);
+        let (winner, loser) = (&engines.regions[0], &engines.regions[1]);
+        let races = [
+            winner
+                .timed(future::ok(vec![Signature::default()]))
+                .boxed_local(),
+            loser.timed(future::pending()).boxed_local(),
+        ];
+        select_ok(races).await.unwrap();
+
+        let stats = engines.stats();
+        let outcomes: Vec<_> = stats
+            .iter()
+            .map(|region| (region.sent, region.landed, region.failed, region.cancelled))
+            .collect();
+        assert_eq!(outcomes, [(1, 1, 0, 0), (1, 0, 0, 1)]);
+    }
+
+    #[test]
+    fn regions_resolve_to_urls() {
+        assert_eq!(
+            resolve_jito_regions("ny, slc").unwrap(),
+            [
+                "https://ny.mainnet.block-engine.jito.wtf",
+                "https://slc.mainnet.block-engine.jito.wtf",
+            ]
+        );
+        assert_eq!(
+            resolve_jito_regions("mainnet,http://127.0.0.1:9100").unwrap(),
+            [
+                "https://mainnet.block-engine.jito.wtf",
+                "http://127.0.0.1:9100"
+            ]
+        );
+    }
+
+    #[test]
+    fn all_regions_leave_out_the_load_balancer() {
+        let urls = resolve_jito_regions("all").unwrap();
+        assert_eq!(urls.len(), JITO_REGIONS.len() - 1);
+        assert!(!urls.contains(&"https://mainnet.block-engine.jito.wtf".to_string()));
+    }
+
+    #[test]
+    fn duplicate_regions_are_raced_once() {
+        let urls = resolve_jito_regions("ny,all,ny,,").unwrap();
+        assert_eq!(urls.len(), JITO_REGIONS.len() - 1);
+        assert_eq!(urls[0], "https://ny.mainnet.block-engine.jito.wtf");
+        assert!(resolve_jito_regions("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_regions_are_rejected() {
+        let err = resolve_jito_regions("ny,mars").unwrap_err();
+        assert!(err.to_string().contains("mars"), "{}", err);
+    }
+}
@@ -0,0 +1,461 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::Keypair;
+use anyhow::{anyhow, bail, Result};
+use bip39::{Language, Mnemonic, Seed};
+use ethers::signers::coins_bip39::English;
+use ethers::signers::{LocalWallet, MnemonicBuilder};
+use ethers::types::Address;
+use serde::Deserialize;
+use solana_sdk::bs58;
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::signer::keypair::{keypair_from_seed_and_derivation_path, read_keypair_file};
+
+use crate::signer::{
+    MantisEthereumSigner, MantisSolanaSigner, RemoteEthereumSigner, RemoteSolanaSigner,
+};
+
+/// Where a signing key is read from.
+///
+/// In `mantis.toml` a source is written as a table with a single key, e.g.
+/// `solana_key = { env = "SOLANA_KEYPAIR" }` or
+/// `solana_key = { file = "~/.config/solana/id.json" }`.
+///
+/// A remote source is written as
+/// `ethereum_key = { remote = { url = "http://localhost:9000", key = "0x..." } }`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Environment variable holding the key in the same format as
+    /// `SOLANA_KEYPAIR` / `ETHEREUM_PKEY`.
+    Env(String),
+    /// Solana CLI JSON keypair file (a byte array), or a file holding a hex
+    /// Ethereum private key.
+    File(PathBuf),
+    /// Encrypted V3 JSON keystore, as written by geth or foundry. The
+    /// passphrase is prompted for unless a password file or fd is given.
+    Keystore {
+        path: PathBuf,
+        #[serde(default)]
+        password_file: Option<PathBuf>,
+        #[serde(default)]
+        password_fd: Option<u32>,
+    },
+    /// BIP39 seed phrase, read from `file` or prompted for on the terminal.
+    ///
+    /// The same phrase can back both chains: `account` selects
+    /// `m/44'/501'/{account}'/0'` on Solana and `m/44'/60'/0'/0/{account}` on
+    /// Ethereum, unless `derivation_path` is given.
+    Mnemonic {
+        #[serde(default)]
+        file: Option<PathBuf>,
+        #[serde(default)]
+        derivation_path: Option<String>,
+        #[serde(default)]
+        account: Option<u32>,
+    },
+    /// Key held by a remote signing service, see [`crate::signer`]. `key` is
+    /// the base58 public key on Solana and the address on Ethereum.
+    Remote { url: String, key: String },
+}
+
+impl KeySource {
+    /// Parse the value of `--keypair`: a path to a JSON keypair file, or
+    /// `prompt` (also `prompt://`) to type a seed phrase.
+    pub fn from_keypair_arg(keypair: &str, derivation_path: Option<String>) -> Self {
+        match keypair {
+            "prompt" | "prompt://" => Self::Mnemonic {
+                file: None,
+                derivation_path,
+                account: None,
+            },
+            path => Self::File(PathBuf::from(path)),
+        }
+    }
+
+    /// This source moved `offset` accounts further along its seed phrase.
+    /// Other sources hold a single key, returned for an offset of zero only.
+    pub fn offset_account(&self, offset: u32) -> Option<Self> {
+        match self {
+            Self::Mnemonic {
+                file,
+                derivation_path: None,
+                account,
+            } => Some(Self::Mnemonic {
+                file: file.clone(),
+                derivation_path: None,
+                account: Some(account.unwrap_or_default() + offset),
+            }),
+            _ if offset == 0 => Some(self.clone()),
+            _ => None,
+        }
+    }
+
+    /// Account index of a seed phrase source without a custom path.
+    pub fn account(&self) -> Option<u32> {
+        match self {
+            Self::Mnemonic {
+                derivation_path: None,
+                account,
+                ..
+            } => Some(account.unwrap_or_default()),
+            _ => None,
+        }
+    }
+
+    pub fn solana_signer(&self) -> Result<MantisSolanaSigner> {
+        match self {
+            Self::Env(var) => solana_keypair_from_base58(&read_env(var)?).map(Into::into),
+            Self::File(path) => solana_keypair_from_file(path).map(Into::into),
+            Self::Keystore { .. } => bail!("Keystores are only supported for Ethereum keys"),
+            Self::Mnemonic {
+                file,
+                derivation_path,
+                account,
+            } => {
+                let path = solana_derivation_path(
+                    derivation_path.as_deref(),
+                    account.unwrap_or_default(),
+                )?;
+                solana_keypair_from_mnemonic(&read_mnemonic(file.as_deref())?, &path)
+                    .map(Into::into)
+            }
+            Self::Remote { url, key } => {
+                let pubkey = Pubkey::from_str(key)
+                    .map_err(|e| anyhow!("Invalid remote signer public key {}: {}", key, e))?;
+                Ok(RemoteSolanaSigner::new(url.clone(), pubkey).into())
+            }
+        }
+    }
+
+    pub fn ethereum_signer(&self) -> Result<MantisEthereumSigner> {
+        match self {
+            Self::Env(var) => ethereum_wallet_from_hex(&read_env(var)?).map(Into::into),
+            Self::File(path) => ethereum_wallet_from_hex(&read_file(path)?).map(Into::into),
+            Self::Keystore {
+                path,
+                password_file,
+                password_fd,
+            } => {
+                let passphrase = Passphrase {
+                    password_file: password_file.clone(),
+                    password_fd: *password_fd,
+                };
+                ethereum_wallet_from_keystore(path, &passphrase).map(Into::into)
+            }
+            Self::Mnemonic {
+                file,
+                derivation_path,
+                account,
+            } => {
+                let path = ethereum_derivation_path(
+                    derivation_path.as_deref(),
+                    account.unwrap_or_default(),
+                );
+                ethereum_wallet_from_mnemonic(&read_mnemonic(file.as_deref())?, &path)
+                    .map(Into::into)
+            }
+            Self::Remote { url, key } => {
+                let address = Address::from_str(key)
+                    .map_err(|e| anyhow!("Invalid remote signer address {}: {}", key, e))?;
+                Ok(RemoteEthereumSigner::new(url.clone(), address).into())
+            }
+        }
+    }
+}
+
+/// Where the passphrase of a keystore is read from. With neither field set
+/// it is prompted for on the terminal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Passphrase {
+    /// File whose first line is the passphrase.
+    pub password_file: Option<PathBuf>,
+    /// Open file descriptor to read the passphrase from, e.g. `3` with
+    /// `3< <(pass show eth)`.
+    pub password_fd: Option<u32>,
+}
+
+impl Passphrase {
+    pub fn read(&self, prompt: &str) -> Result<String> {
+        let passphrase = match (&self.password_file, self.password_fd) {
+            (Some(_), Some(_)) => bail!("Set either a password file or a password fd, not both"),
+            (Some(file), None) => read_file(file)?,
+            (None, Some(fd)) => read_file(Path::new(&format!("/dev/fd/{}", fd)))?,
+            (None, None) => rpassword::prompt_password(prompt)
+                .map_err(|e| anyhow!("Failed to read the passphrase: {}", e))?,
+        };
+        // Only the first line counts, so files ending with a newline work.
+        Ok(passphrase.lines().next().unwrap_or_default().to_string())
+    }
+}
+
+/// Parse a base58 encoded 64 byte Solana keypair, as exported by Phantom.
+pub fn solana_keypair_from_base58(private_key: &str) -> Result<Keypair> {
+    let private_key_bytes = bs58::decode(private_key.trim())
+        .into_vec()
+        .map_err(|e| anyhow!("Failed to decode Base58 private key: {}", e))?;
+    Keypair::from_bytes(&private_key_bytes).map_err(|e| anyhow!("Failed to create keypair: {}", e))
+}
+
+/// Read a Solana CLI keypair file, a JSON array of the 64 keypair bytes.
+pub fn solana_keypair_from_file(path: &Path) -> Result<Keypair> {
+    let path = expand_home(path);
+    read_keypair_file(&path)
+        .map_err(|e| anyhow!("Failed to read keypair file {}: {}", path.display(), e))
+}
+
+/// Derive a Solana keypair from a BIP39 seed phrase, without passphrase.
+pub fn solana_keypair_from_mnemonic(
+    phrase: &str,
+    derivation_path: &DerivationPath,
+) -> Result<Keypair> {
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|e| anyhow!("Invalid seed phrase: {}", e))?;
+    let seed = Seed::new(&mnemonic, "");
+
+    keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(derivation_path.clone()))
+        .map_err(|e| anyhow!("Failed to derive keypair: {}", e))
+}
+
+/// Derive an Ethereum wallet from a BIP39 seed phrase, without passphrase.
+pub fn ethereum_wallet_from_mnemonic(phrase: &str, derivation_path: &str) -> Result<LocalWallet> {
+    MnemonicBuilder::<English>::default()
+        .phrase(phrase.trim())
+        .derivation_path(derivation_path)
+        .map_err(|e| anyhow!("Invalid derivation path {}: {}", derivation_path, e))?
+        .build()
+        .map_err(|e| anyhow!("Failed to derive wallet: {}", e))
+}
+
+/// `derivation_path`, or the path of `account` used by the Solana CLI and
+/// Phantom.
+pub fn solana_derivation_path(
+    derivation_path: Option<&str>,
+    account: u32,
+) -> Result<DerivationPath> {
+    match derivation_path {
+        Some(path) => DerivationPath::from_absolute_path_str(path)
+            .map_err(|e| anyhow!("Invalid derivation path {}: {}", path, e)),
+        None => Ok(DerivationPath::new_bip44(Some(account), Some(0))),
+    }
+}
+
+/// `derivation_path`, or the path of `account` used by MetaMask.
+pub fn ethereum_derivation_path(derivation_path: Option<&str>, account: u32) -> String {
+    match derivation_path {
+        Some(path) => path.to_string(),
+        None => format!("m/44'/60'/0'/0/{}", account),
+    }
+}
+
+/// Parse a hex encoded Ethereum private key, with or without `0x`.
+pub fn ethereum_wallet_from_hex(private_key: &str) -> Result<LocalWallet> {
+    private_key
+        .trim()
+        .trim_start_matches("0x")
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse Ethereum private key: {}", e))
+}
+
+/// Decrypt an encrypted V3 JSON keystore.
+pub fn ethereum_wallet_from_keystore(path: &Path, passphrase: &Passphrase) -> Result<LocalWallet> {
+    let path = expand_home(path);
+    let passphrase = passphrase.read(&format!("Passphrase for {}: ", path.display()))?;
+    LocalWallet::decrypt_keystore(&path, passphrase)
+        .map_err(|e| anyhow!("Failed to decrypt keystore {}: {}", path.display(), e))
+}
+
+fn read_env(var: &str) -> Result<String> {
+    env::var(var).map_err(|_| anyhow!("Environment variable {} is not set", var))
+}
+
+fn read_file(path: &Path) -> Result<String> {
+    let path = expand_home(path);
+    fs::read_to_string(&path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))
+}
+
+/// Read a seed phrase from `file`, or from the terminal without echo.
+///
+/// A prompted phrase is remembered, so keys of both chains derived from it
+/// only ask once.
+pub fn read_mnemonic(file: Option<&Path>) -> Result<String> {
+    static PROMPTED: OnceLock<String> = OnceLock::new();
+
+    match file {
+        Some(file) => read_file(file),
+        None => {
+            if let Some(phrase) = PROMPTED.get() {
+                return Ok(phrase.clone());
+            }
+            let phrase = rpassword::prompt_password("Seed phrase: ")
+                .map_err(|e| anyhow!("Failed to read the seed phrase: {}", e))?;
+            Ok(PROMPTED.get_or_init(|| phrase).clone())
+        }
+    }
+}
+
+/// Expand a leading `~/` to the home directory, as shells do.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::Signer as _;
+    use solana_sdk::signer::Signer as _;
+
+    /// Seed phrase of the Hardhat and Anvil development accounts.
+    const PHRASE: &str = "test test test test test test test test test test test junk";
+
+    /// Test vector of the Web3 Secret Storage definition, encrypted with
+    /// "testpassword".
+    const KEYSTORE: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf": "pbkdf2",
+            "kdfparams": {
+                "c": 262144,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    /// Private key held by [`KEYSTORE`].
+    const KEYSTORE_KEY: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("mantis-keys-{}-{}", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn keystores_decrypt_to_their_key() {
+        let keystore = temp_file("keystore.json", KEYSTORE);
+        let password_file = temp_file("password.txt", "testpassword\n");
+        let passphrase = Passphrase {
+            password_file: Some(password_file.clone()),
+            password_fd: None,
+        };
+
+        let wallet = ethereum_wallet_from_keystore(&keystore, &passphrase).unwrap();
+        let expected = ethereum_wallet_from_hex(KEYSTORE_KEY).unwrap();
+        assert_eq!(wallet.address(), expected.address());
+
+        fs::remove_file(&keystore).unwrap();
+        fs::remove_file(&password_file).unwrap();
+    }
+
+    #[test]
+    fn keystores_reject_a_wrong_passphrase() {
+        let keystore = temp_file("wrong-keystore.json", KEYSTORE);
+        let password_file = temp_file("wrong-password.txt", "wrongpassword");
+        let passphrase = Passphrase {
+            password_file: Some(password_file.clone()),
+            password_fd: None,
+        };
+
+        let err = ethereum_wallet_from_keystore(&keystore, &passphrase).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Failed to decrypt keystore"),
+            "{}",
+            err
+        );
+
+        fs::remove_file(&keystore).unwrap();
+        fs::remove_file(&password_file).unwrap();
+    }
+
+    #[test]
+    fn passphrases_are_read_from_a_file_descriptor() {
+        use std::os::unix::io::AsRawFd;
+
+        let path = temp_file("fd-password.txt", "from fd\nsecond line\n");
+        let file = fs::File::open(&path).unwrap();
+        let passphrase = Passphrase {
+            password_file: None,
+            password_fd: Some(file.as_raw_fd() as u32),
+        };
+        assert_eq!(passphrase.read("unused").unwrap(), "from fd");
+
+        let both = Passphrase {
+            password_file: Some(path.clone()),
+            ..passphrase
+        };
+        assert!(both.read("unused").is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unset_key_variables_are_reported() {
+        let source = KeySource::Env("MANTIS_TEST_UNSET_KEY".to_string());
+        let err = source.ethereum_signer().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Environment variable MANTIS_TEST_UNSET_KEY is not set"
+        );
+    }
+
+    #[test]
+    fn solana_keys_follow_the_solana_cli_path() {
+        for (account, pubkey) in [
+            (0, "oeYf6KAJkLYhBuR8CiGc6L4D4Xtfepr85fuDgA9kq96"),
+            (1, "AqynRZwvVqUPRwRJXvm6odUb3t93fDjnWe3p6BeuUFxD"),
+        ] {
+            let path = solana_derivation_path(None, account).unwrap();
+            assert_eq!(path.to_string(), format!("m/44'/501'/{}'/0'", account));
+            let keypair = solana_keypair_from_mnemonic(PHRASE, &path).unwrap();
+            assert_eq!(keypair.pubkey().to_string(), pubkey);
+        }
+    }
+
+    #[test]
+    fn solana_keys_follow_a_custom_path() {
+        let path = solana_derivation_path(Some("m/44'/501'"), 5).unwrap();
+        let keypair = solana_keypair_from_mnemonic(PHRASE, &path).unwrap();
+        assert_eq!(
+            keypair.pubkey().to_string(),
+            "9tKf8Q98FsGKJiM4oqMnTxmYH3fU2qJzSwzc76vgzyBT"
+        );
+        assert!(solana_derivation_path(Some("m/44'/501'/x'"), 0).is_err());
+    }
+
+    #[test]
+    fn ethereum_wallets_follow_the_metamask_path() {
+        for (account, address) in [
+            (0, "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"),
+            (1, "0x70997970c51812dc3a010c7d01b50e0d17dc79c8"),
+            (2, "0x3c44cdddb6a900fa2b585dd299e03d12fa4293bc"),
+        ] {
+            let path = ethereum_derivation_path(None, account);
+            assert_eq!(path, format!("m/44'/60'/0'/0/{}", account));
+            let wallet = ethereum_wallet_from_mnemonic(PHRASE, &path).unwrap();
+            assert_eq!(format!("{:?}", wallet.address()), address);
+        }
+    }
+
+    #[test]
+    fn invalid_seed_phrases_are_rejected() {
+        let path = solana_derivation_path(None, 0).unwrap();
+        let phrase = "test test test test test test test test test test test test";
+        assert!(solana_keypair_from_mnemonic(phrase, &path).is_err());
+        assert!(ethereum_wallet_from_mnemonic(phrase, "m/44'/60'/0'/0/0").is_err());
+    }
+}
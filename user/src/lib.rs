@@ -0,0 +1,40 @@
+pub mod address;
+pub mod amount;
+pub mod auctioneer;
+pub mod client;
+pub mod config;
+pub mod ethereum;
+pub mod intent;
+pub mod jito;
+pub mod keys;
+pub mod network;
+pub mod offline;
+pub mod permit;
+pub mod signer;
+pub mod solana;
+
+/// HTTP server of the stand-ins in `examples/`, for tests talking HTTP.
+#[cfg(test)]
+#[path = "../examples/common/http.rs"]
+mod http;
+
+use rand::{distributions::Alphanumeric, Rng};
+
+pub use crate::address::DomainAddress;
+pub use crate::amount::TokenAmount;
+pub use crate::client::{EscrowReceipt, MantisClient};
+pub use crate::intent::{Domain, EscrowOptions, IntentRequest, IntentRequestBuilder};
+pub use crate::network::{Network, NetworkName};
+
+/// Mainnet auctioneer, see [`Network`] for other networks.
+pub const AUCTIONEER_URL: &str =
+    "https://auctioneer-2.composable-shared-artifacts.composablenodes.tech/api/v1-beta";
+
+/// Generate a random intent ID.
+pub fn generate_random_intent_id() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(6)
+        .map(char::from)
+        .collect()
+}
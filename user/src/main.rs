@@ -1,281 +1,553 @@
 mod cli;
-mod ethereum;
-mod solana;
 
 use std::env;
-use std::sync::Arc;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
 
-use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
-use anchor_client::solana_sdk::pubkey::Pubkey;
-use anchor_client::solana_sdk::signature::Keypair;
-use anchor_client::{Client, Cluster};
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use clap::parser::ValueSource;
 use clap::ArgMatches;
-use ethers::types::Address;
+use ethers::signers::Signer as _;
 use ethers::types::U256;
-use rand::{distributions::Alphanumeric, Rng};
-use serde::{Deserialize, Serialize};
-use solana_sdk::bs58;
-use solana_sdk::signature::{Signature, Signer};
-use std::str::FromStr;
+use mantis_sdk::address::DomainAddress;
+use mantis_sdk::amount::TokenAmount;
+use mantis_sdk::config::{Config, Profile};
+use mantis_sdk::ethereum::ApprovalAmount;
+use mantis_sdk::intent::{Domain, IntentRequest};
+use mantis_sdk::keys::KeySource;
+use mantis_sdk::offline::OfflineTransaction;
+use mantis_sdk::solana::{DurableNonce, TxSendMethod};
+use mantis_sdk::{EscrowOptions, EscrowReceipt, MantisClient, Network, NetworkName};
+use solana_sdk::signer::Signer as _;
 
 use crate::cli::parse_cli;
-use crate::cli::parse_common_args;
-use crate::ethereum::escrow_and_store_intent_ethereum;
-use crate::solana::{
-    escrow_and_store_intent_cross_chain_solana, escrow_and_store_intent_solana, TxSendMethod,
-};
-
-const AUCTIONEER_URL: &str =
-    "https://auctioneer-2.composable-shared-artifacts.composablenodes.tech/api/v1-beta";
+use crate::cli::parse_intent_request;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<()> {
     dotenv::dotenv().ok();
     let matches = parse_cli();
+    let config_path = matches
+        .get_one::<PathBuf>("config")
+        .cloned()
+        .or_else(|| env::var_os("MANTIS_CONFIG").map(PathBuf::from));
+    let config = Config::load_or_default(config_path)?;
+    let mut profile = config
+        .profile(matches.get_one::<String>("profile").map(String::as_str))?
+        .with_env_overrides();
+
+    // Key flags take precedence over the environment and the profile.
+    if let Some(keypair) = matches.get_one::<String>("keypair") {
+        profile.solana_key = Some(KeySource::from_keypair_arg(
+            keypair,
+            matches.get_one::<String>("derivation_path").cloned(),
+        ));
+    }
+    if let Some(fee_payer) = matches.get_one::<String>("fee_payer") {
+        profile.solana_fee_payer = Some(KeySource::from_keypair_arg(fee_payer, None));
+    }
+    if let Some(mnemonic) = matches.get_one::<String>("mnemonic") {
+        let source = KeySource::Mnemonic {
+            file: match mnemonic.as_str() {
+                "prompt" | "prompt://" => None,
+                file => Some(PathBuf::from(file)),
+            },
+            derivation_path: None,
+            account: matches.get_one::<u32>("account").copied(),
+        };
+        profile.solana_key = Some(source.clone());
+        profile.ethereum_key = Some(source);
+    }
+    if let Some(keystore) = matches.get_one::<PathBuf>("keystore") {
+        profile.ethereum_key = Some(KeySource::Keystore {
+            path: keystore.clone(),
+            password_file: matches.get_one::<PathBuf>("password_file").cloned(),
+            password_fd: matches.get_one::<u32>("password_fd").copied(),
+        });
+    }
+
+    if let Some(price) = matches.get_one::<u64>("priority_fee") {
+        profile.priority_fee.price = Some(*price);
+    }
+    if let Some(min_price) = matches.get_one::<u64>("min_priority_fee") {
+        profile.priority_fee.min_price = Some(*min_price);
+    }
+    if let Some(max_price) = matches.get_one::<u64>("max_priority_fee") {
+        profile.priority_fee.max_price = Some(*max_price);
+    }
+    if let Some(limit) = matches.get_one::<u32>("compute_unit_limit") {
+        profile.priority_fee.compute_unit_limit = Some(*limit);
+    }
+
+    if let Some(tip_strategy) = matches.get_one::<String>("jito_tip_strategy") {
+        profile.jito.tip_strategy = Some(tip_strategy.clone());
+    }
+    if let Some(max_tip) = matches.get_one::<u64>("max_jito_tip") {
+        profile.jito.max_tip_amount = Some(*max_tip);
+    }
+    if let Some(regions) = matches.get_one::<String>("jito_regions") {
+        profile.jito.regions = Some(regions.clone());
+    }
+    if let Some(race) = matches.get_one::<usize>("jito_race") {
+        profile.jito.race = Some(*race);
+    }
+    if let Some(auth_keypair) = matches.get_one::<String>("jito_auth_keypair") {
+        profile.jito.auth_key = Some(KeySource::from_keypair_arg(auth_keypair, None));
+    }
+
+    // Showing keys and signing need no RPC connection.
+    if let Some(keys_matches) = matches.subcommand_matches("keys") {
+        if let Some(show_matches) = keys_matches.subcommand_matches("show") {
+            handle_keys_show(&profile, show_matches)?;
+        }
+        return Ok(());
+    }
+    if let Some(sign_matches) = matches.subcommand_matches("sign") {
+        handle_sign(&profile, sign_matches).await?;
+        return Ok(());
+    }
+
+    // An explicit --network wins over the profile's network.
+    let network = match matches.value_source("network") {
+        Some(ValueSource::CommandLine) => *matches.get_one::<NetworkName>("network").unwrap(),
+        _ => profile.network.unwrap_or_default(),
+    };
+    let client = MantisClient::from_profile(Network::new(network), &profile).await?;
 
     // Execute the appropriate function based on the subcommand used
     if let Some(solana_matches) = matches.subcommand_matches("solana") {
-        let solana_matches_cloned = solana_matches.clone();
-        handle_solana_single_domain_intent(&solana_matches_cloned)
-            .await
-            .expect("Failed to execute blocking code on solana");
+        handle_intent(
+            &client,
+            &profile,
+            solana_matches,
+            Domain::Solana,
+            Domain::Solana,
+        )
+        .await?;
     } else if let Some(solana_ethereum_matches) = matches.subcommand_matches("solana-ethereum") {
-        let solana_ethereum_matches_cloned = solana_ethereum_matches.clone();
-        handle_solana_ethereum_cross_domain_intent(&solana_ethereum_matches_cloned)
-            .await
-            .expect("Failed to execute blocking code on solana-ethereum");
+        handle_intent(
+            &client,
+            &profile,
+            solana_ethereum_matches,
+            Domain::Solana,
+            Domain::Ethereum,
+        )
+        .await?;
     } else if let Some(ethereum_matches) = matches.subcommand_matches("ethereum") {
-        let ethereum_matches_cloned = ethereum_matches.clone();
-        handle_ethereum_single_domain_intent(&ethereum_matches_cloned)
-            .await
-            .unwrap();
+        handle_intent(
+            &client,
+            &profile,
+            ethereum_matches,
+            Domain::Ethereum,
+            Domain::Ethereum,
+        )
+        .await?;
     } else if let Some(ethereum_solana_matches) = matches.subcommand_matches("ethereum-solana") {
-        handle_ethereum_solana_cross_domain_intent(&ethereum_solana_matches)
-            .await
-            .unwrap();
+        handle_intent(
+            &client,
+            &profile,
+            ethereum_solana_matches,
+            Domain::Ethereum,
+            Domain::Solana,
+        )
+        .await?;
+    } else if let Some(submit_matches) = matches.subcommand_matches("submit") {
+        handle_submit_intent_file(&client, submit_matches).await?;
+    } else if let Some(cancel_matches) = matches.subcommand_matches("cancel") {
+        handle_cancel_intent_solana(&client, cancel_matches).await?;
+    } else if let Some(cancel_matches) = matches.subcommand_matches("ethereum-cancel") {
+        handle_cancel_intent_ethereum(&client, cancel_matches).await?;
+    } else if let Some(approve_matches) = matches.subcommand_matches("approve") {
+        handle_approve_erc20(&client, &profile, approve_matches).await?;
+    } else if let Some(build_matches) = matches.subcommand_matches("build") {
+        handle_build(&client, build_matches).await?;
+    } else if let Some(broadcast_matches) = matches.subcommand_matches("broadcast") {
+        handle_broadcast(&client, broadcast_matches).await?;
     }
 
     Ok(())
 }
 
-/// Handle the Ethereum -> Ethereum single domain intent.
-async fn handle_ethereum_single_domain_intent(matches: &ArgMatches) -> Result<()> {
-    let token_in = Address::from_str(matches.get_one::<String>("token_in").unwrap()).unwrap();
-    let amount_in = U256::from(*matches.get_one::<u64>("amount_in").unwrap());
-    let token_out = matches.get_one::<String>("token_out").unwrap().to_string();
-    let amount_out = U256::from(*matches.get_one::<u64>("amount_out").unwrap());
-    let timeout = U256::from(*matches.get_one::<u64>("timeout").unwrap());
-
-    // Call the escrow function for single domain
-    match escrow_and_store_intent_ethereum(
-        token_in,
-        amount_in,
-        token_out,
-        amount_out,
-        String::default(),
-        true,
-        timeout,
-    )
-    .await
-    {
-        Ok(receipt) => {
-            println!(
-                "Transaction successful, receipt: {:?}",
-                receipt.transaction_hash
-            );
-        }
-        Err(e) => {
-            println!("Transaction failed ** Remember you need to approve <TOKEN_IN> to Escrow SC 0x64E78873057769a5fd9A2278E6820666ec7e87f9 **: {:?}", e);
-        }
+/// Handle an intent given on the command line, for any pair of domains.
+async fn handle_intent(
+    client: &MantisClient,
+    profile: &Profile,
+    matches: &ArgMatches,
+    src_domain: Domain,
+    dst_domain: Domain,
+) -> Result<()> {
+    let intent = parse_intent_request(client, profile, matches, src_domain, dst_domain).await?;
+
+    if let Some(path) = matches.get_one::<PathBuf>("save") {
+        intent.save(path)?;
+        println!("Intent saved to {}", path.display());
     }
 
-    Ok(())
+    submit_intent(client, &intent, &escrow_options(matches)).await
 }
 
-/// Handle the Ethereum -> Solana cross-domain intent.
-async fn handle_ethereum_solana_cross_domain_intent(matches: &ArgMatches) -> Result<()> {
-    let token_in = Address::from_str(matches.get_one::<String>("token_in").unwrap()).unwrap();
-    let amount_in = U256::from(*matches.get_one::<u64>("amount_in").unwrap());
-    let token_out = matches.get_one::<String>("token_out").unwrap().to_string();
-    let amount_out = U256::from(*matches.get_one::<u64>("amount_out").unwrap());
-    let timeout = U256::from(*matches.get_one::<u64>("timeout").unwrap());
-    let dst_user = matches.get_one::<String>("dst_user").unwrap().to_string();
-
-    // Call the escrow function for cross domain
-    match escrow_and_store_intent_ethereum(
-        token_in, amount_in, token_out, amount_out, dst_user, false, timeout,
-    )
-    .await
-    {
-        Ok(receipt) => {
-            println!(
-                "Transaction successful, receipt: {:?}",
-                receipt.transaction_hash
-            );
-        }
-        Err(e) => {
-            println!("Transaction failed ** Remember you need to approve <TOKEN_IN> to Escrow SC 0x64E78873057769a5fd9A2278E6820666ec7e87f9 **: {:?}", e);
-        }
-    }
+/// Handle an intent previously saved with `--save`.
+async fn handle_submit_intent_file(client: &MantisClient, matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<PathBuf>("intent_file").unwrap();
+    let intent = IntentRequest::load(path)?;
+
+    submit_intent(client, &intent, &escrow_options(matches)).await
+}
+
+/// Handle the cancellation of a timed out Solana intent.
+async fn handle_cancel_intent_solana(client: &MantisClient, matches: &ArgMatches) -> Result<()> {
+    let intent_id = matches.get_one::<String>("intent_id").unwrap();
+
+    let result = client
+        .cancel_intent_solana(intent_id, tx_send_method(matches))
+        .await?;
+    println!(
+        "Intent {} cancelled, signature: {}",
+        intent_id, result.signature
+    );
+    print_jito_tip(client, result.jito_tip);
 
     Ok(())
 }
 
-/// Handle the Solana -> Solana intent.
-async fn handle_solana_single_domain_intent(matches: &ArgMatches) -> Result<()> {
-    let private_key_bytes =
-        bs58::decode(env::var("SOLANA_KEYPAIR").expect("SOLANA_KEYPAIR must be set"))
-            .into_vec()
-            .expect("Failed to decode Base58 private key");
-    let auctioneer_url = env::var("AUCTIONEER_URL").unwrap_or(AUCTIONEER_URL.to_string());
-    let tx_send_method: TxSendMethod = *matches
-        .get_one::<TxSendMethod>("tx_send_method")
-        .expect("tx-send-method is required");
-
-    let wallet =
-        Arc::new(Keypair::from_bytes(&private_key_bytes).expect("Failed to create keypair"));
-    println!("Sender: {:?}", wallet.pubkey());
-    let auctioneer_state = Pubkey::find_program_address(&[b"auctioneer"], &bridge_escrow::ID).0;
-
-    let client = Client::new_with_options(
-        Cluster::Mainnet,
-        wallet.clone(),
-        CommitmentConfig::processed(),
+/// Handle the withdrawal of a timed out Ethereum intent.
+async fn handle_cancel_intent_ethereum(client: &MantisClient, matches: &ArgMatches) -> Result<()> {
+    let intent_id = matches.get_one::<String>("intent_id").unwrap();
+    let intent_id = U256::from_dec_str(intent_id)
+        .map_err(|e| anyhow!("Invalid intent id {}: {:?}", intent_id, e))?;
+
+    let receipt = client.cancel_intent_ethereum(intent_id).await?;
+    println!(
+        "Intent {} withdrawn, receipt: {:?}",
+        intent_id, receipt.transaction_hash
     );
 
-    let intent_id = generate_random_intent_id();
-    let (amount_in, token_in, token_out, amount_out, timeout_duration) = parse_common_args(matches);
-
-    let dst_user = wallet.pubkey();
-    let single_domain = true;
-
-    match escrow_and_store_intent_solana(
-        &wallet,
-        auctioneer_state,
-        &client,
-        intent_id,
-        amount_in,
-        token_in,
-        dst_user,
-        token_out,
-        amount_out,
-        timeout_duration,
-        single_domain,
-        tx_send_method,
-    )
-    .await
-    {
-        Ok(sig) => {
-            println!("Transaction successful, signature: {}", sig);
-            send_signature_to_auctioneer(&auctioneer_url, sig).await?;
-            sig
-        }
-        Err(e) => {
-            println!("Transaction failed ** Remember you need to create a token_account for Escrow Program first **: {}", e);
-            return Err(anyhow::anyhow!(e));
-        }
+    Ok(())
+}
+
+/// Handle setting the ERC20 allowance of the escrow contract.
+async fn handle_approve_erc20(
+    client: &MantisClient,
+    profile: &Profile,
+    matches: &ArgMatches,
+) -> Result<()> {
+    let token = profile.resolve_token(
+        Domain::Ethereum,
+        matches.get_one::<String>("token").unwrap(),
+    );
+    let token = DomainAddress::parse(Domain::Ethereum, token)?;
+    let spender = matches
+        .get_one::<String>("spender")
+        .map(|spender| DomainAddress::parse(Domain::Ethereum, spender)?.as_ethereum())
+        .transpose()?;
+
+    let amount = if matches.get_flag("unlimited") {
+        ApprovalAmount::Unlimited
+    } else if matches.get_flag("revoke") {
+        ApprovalAmount::Revoke
+    } else {
+        let amount = matches
+            .get_one::<String>("amount")
+            .ok_or_else(|| anyhow!("amount is required unless --unlimited or --revoke is set"))?;
+        let amount = if matches.get_flag("ui_amounts") {
+            client.ui_amount(&token, amount).await?
+        } else {
+            TokenAmount::parse_raw(amount)?
+        };
+        ApprovalAmount::Exact(amount.raw())
     };
 
+    let receipt = client
+        .approve_erc20(token.as_ethereum()?, spender, amount)
+        .await?;
+    println!(
+        "Approval successful, receipt: {:?}",
+        receipt.transaction_hash
+    );
+
     Ok(())
 }
 
-/// Handle the Solana -> Ethereum cross-domain intent.
-async fn handle_solana_ethereum_cross_domain_intent(matches: &ArgMatches) -> Result<()> {
-    let private_key_bytes =
-        bs58::decode(env::var("SOLANA_KEYPAIR").expect("SOLANA_KEYPAIR must be set"))
-            .into_vec()
-            .expect("Failed to decode Base58 private key");
-    let auctioneer_url = env::var("AUCTIONEER_URL").unwrap_or(AUCTIONEER_URL.to_string());
-    let tx_send_method: TxSendMethod = matches
-        .get_one::<TxSendMethod>("tx_send_method")
-        .map(|x| *x)
-        .unwrap_or_default();
-    dbg!(tx_send_method);
-
-    let wallet =
-        Arc::new(Keypair::from_bytes(&private_key_bytes).expect("Failed to create keypair"));
-
-    let auctioneer_state = Pubkey::find_program_address(&[b"auctioneer"], &bridge_escrow::ID).0;
-
-    let client = Client::new_with_options(
-        Cluster::Mainnet,
-        wallet.clone(),
-        CommitmentConfig::processed(),
+/// Handle building the unsigned escrow transaction of a saved intent.
+async fn handle_build(client: &MantisClient, matches: &ArgMatches) -> Result<()> {
+    let intent = IntentRequest::load(matches.get_one::<PathBuf>("intent_file").unwrap())?;
+    let from = matches
+        .get_one::<String>("from")
+        .map(|from| DomainAddress::parse(intent.src_domain, from))
+        .transpose()?;
+    let fee_payer = matches
+        .get_one::<String>("fee_payer_address")
+        .map(|fee_payer| DomainAddress::parse(Domain::Solana, fee_payer)?.as_solana())
+        .transpose()?;
+    let nonce = match matches.get_one::<String>("nonce_account") {
+        Some(account) => Some(DurableNonce {
+            account: DomainAddress::parse(Domain::Solana, account)?.as_solana()?,
+            authority: matches
+                .get_one::<String>("nonce_authority")
+                .map(|authority| DomainAddress::parse(Domain::Solana, authority)?.as_solana())
+                .transpose()?,
+        }),
+        None => None,
+    };
+
+    let transaction = client
+        .build_intent(&intent, from, fee_payer, &escrow_options(matches), nonce)
+        .await?;
+    let out = matches.get_one::<PathBuf>("out").unwrap();
+    transaction.save(out)?;
+    println!(
+        "Unsigned {} transaction written to {}",
+        transaction.domain(),
+        out.display()
     );
+    if transaction.domain() == Domain::Solana && nonce.is_none() {
+        println!("It expires in about a minute, use --nonce-account to sign it later");
+    }
+
+    Ok(())
+}
 
-    let intent_id = generate_random_intent_id();
-    let (amount_in, token_in, token_out, amount_out, timeout_duration) = parse_common_args(matches);
-
-    let dst_user = matches.get_one::<String>("dst_user").unwrap().to_string();
-    let single_domain = false;
-
-    match escrow_and_store_intent_cross_chain_solana(
-        &wallet,
-        auctioneer_state,
-        &client,
-        intent_id,
-        amount_in,
-        token_in,
-        dst_user,
-        token_out,
-        amount_out,
-        timeout_duration,
-        single_domain,
-        tx_send_method,
-    )
-    .await
-    {
-        Ok(sig) => {
-            println!("Transaction successful, signature: {}", sig);
-            send_signature_to_auctioneer(&auctioneer_url, sig).await?;
-            sig
+/// Handle signing a transaction file with the configured key of its domain.
+async fn handle_sign(profile: &Profile, matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<PathBuf>("transaction_file").unwrap();
+    let mut transaction = OfflineTransaction::load(path)?;
+
+    let summary = transaction.verify()?;
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    if !matches.get_flag("yes") && !confirm("Sign this transaction?")? {
+        return Err(anyhow!("Signing cancelled"));
+    }
+
+    match transaction.domain() {
+        Domain::Solana => {
+            // The owner and a separate fee payer may both have to sign.
+            let mut signed = false;
+            for source in [&profile.solana_key, &profile.solana_fee_payer]
+                .into_iter()
+                .flatten()
+            {
+                signed |= transaction.sign_solana(&source.solana_signer()?)?;
+            }
+            if !signed {
+                return Err(anyhow!("No configured Solana key signs this transaction"));
+            }
         }
-        Err(e) => {
-            println!("Transaction failed ** Remember you need to create a token_account for Escrow Program first **: {}", e);
-            return Err(anyhow::anyhow!(e));
+        Domain::Ethereum => {
+            let source = profile
+                .ethereum_key
+                .as_ref()
+                .ok_or_else(|| anyhow!("No Ethereum key is configured"))?;
+            transaction.sign_ethereum(source.ethereum_signer()?).await?;
         }
-    };
+    }
+    transaction.save(path)?;
+
+    if transaction.is_signed()? {
+        println!(
+            "Transaction signed, broadcast it with `broadcast {}`",
+            path.display()
+        );
+    } else {
+        println!("Signature added, the transaction needs more signatures");
+    }
 
     Ok(())
 }
 
-async fn send_signature_to_auctioneer(auctioneer_url: &str, signature: Signature) -> Result<()> {
-    let request = SolanaTxHashRequest {
-        tx_hash: signature.to_string(),
-    };
+/// Ask `question` on the terminal, true when answered with yes.
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
 
-    let http_response = reqwest::Client::new()
-        .post(format!("{auctioneer_url}/solana_tx_hash"))
-        .json(&request)
-        .send()
-        .await?;
+/// Handle sending a signed transaction file.
+async fn handle_broadcast(client: &MantisClient, matches: &ArgMatches) -> Result<()> {
+    let transaction =
+        OfflineTransaction::load(matches.get_one::<PathBuf>("transaction_file").unwrap())?;
+
+    match client.broadcast(&transaction).await? {
+        EscrowReceipt::Solana {
+            intent_id,
+            signature,
+            jito_tip,
+        } => {
+            println!("Intent {} escrowed, signature: {}", intent_id, signature);
+            print_jito_tip(client, jito_tip);
+        }
+        EscrowReceipt::Ethereum { intent_id, receipt } => {
+            println!(
+                "Transaction successful, receipt: {:?}",
+                receipt.transaction_hash
+            );
+            print_ethereum_intent_id(intent_id);
+        }
+    }
 
-    println!(
-        "Status of solana_tx_hash submission: {}",
-        http_response.status()
-    );
+    Ok(())
+}
 
-    let _response: SolanaTxHashResponse = http_response.json().await?;
+/// Handle printing the addresses of the configured keys, for several
+/// accounts when they come from a seed phrase.
+fn handle_keys_show(profile: &Profile, matches: &ArgMatches) -> Result<()> {
+    if profile.solana_key.is_none() && profile.ethereum_key.is_none() {
+        return Err(anyhow!("No keys are configured"));
+    }
+
+    for offset in 0..*matches.get_one::<u32>("count").unwrap() {
+        let solana = profile
+            .solana_key
+            .as_ref()
+            .and_then(|source| source.offset_account(offset));
+        let ethereum = profile
+            .ethereum_key
+            .as_ref()
+            .and_then(|source| source.offset_account(offset));
+        if solana.is_none() && ethereum.is_none() {
+            break;
+        }
+
+        match solana
+            .as_ref()
+            .or(ethereum.as_ref())
+            .and_then(KeySource::account)
+        {
+            Some(account) => println!("Account {}", account),
+            None => println!("Keys"),
+        }
+        if let Some(source) = solana {
+            println!("  Solana:   {}", source.solana_signer()?.pubkey());
+        }
+        if let Some(source) = ethereum {
+            let address = DomainAddress::Ethereum(source.ethereum_signer()?.address());
+            println!("  Ethereum: {}", address);
+        }
+    }
 
     Ok(())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SolanaTxHashRequest {
-    pub tx_hash: String,
+fn tx_send_method(matches: &ArgMatches) -> TxSendMethod {
+    matches
+        .try_get_one::<TxSendMethod>("tx_send_method")
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or_default()
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SolanaTxHashResponse {
-    pub intent_id: String,
+fn escrow_options(matches: &ArgMatches) -> EscrowOptions {
+    EscrowOptions {
+        tx_send_method: tx_send_method(matches),
+        auto_approve: matches
+            .try_get_one::<bool>("auto_approve")
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or_default(),
+        reject_fee_on_transfer: matches
+            .try_get_one::<bool>("reject_fee_on_transfer")
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or_default(),
+        max_bridge_fee: matches
+            .try_get_one::<U256>("max_bridge_fee")
+            .ok()
+            .flatten()
+            .copied(),
+        bridge_fee: matches
+            .try_get_one::<U256>("bridge_fee")
+            .ok()
+            .flatten()
+            .copied(),
+    }
 }
 
-/// Generate a random intent ID.
-fn generate_random_intent_id() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(6)
-        .map(char::from)
-        .collect()
+/// Print the id of an escrowed Ethereum intent, needed to withdraw it with
+/// `ethereum-cancel`.
+fn print_ethereum_intent_id(intent_id: Option<U256>) {
+    match intent_id {
+        Some(intent_id) => println!("Intent id: {}", intent_id),
+        None => println!("Intent id not found in the transaction logs"),
+    }
+}
+
+/// Print the Jito tip paid by a Solana transaction, if any, and the latency
+/// of every block engine region when bundles are raced.
+fn print_jito_tip(client: &MantisClient, jito_tip: Option<u64>) {
+    let Some(tip) = jito_tip else {
+        return;
+    };
+    println!("Jito tip: {} lamports", tip);
+
+    let stats = client.submit_config().jito_block_engines.stats();
+    if stats.len() < 2 {
+        return;
+    }
+    for region in stats {
+        let latency = |latency: Option<Duration>| {
+            latency.map_or_else(|| "-".to_string(), |latency| format!("{:?}", latency))
+        };
+        println!(
+            "  {}: connect {}, landed {}/{} ({} cancelled) in {} on average",
+            region.url,
+            latency(region.connect_latency),
+            region.landed,
+            region.sent,
+            region.cancelled,
+            latency(region.average_latency()),
+        );
+    }
+}
+
+async fn submit_intent(
+    client: &MantisClient,
+    intent: &IntentRequest,
+    options: &EscrowOptions,
+) -> Result<()> {
+    if intent.src_domain == Domain::Solana {
+        println!("Sender: {:?}", client.solana_pubkey()?);
+    }
+    if intent.src_domain == Domain::Ethereum && !intent.is_single_domain() {
+        let fee = client.cross_domain_fee(options.bridge_fee).await?;
+        println!(
+            "Cross-domain fee: {} ETH ({} wei)",
+            TokenAmount::from_raw(fee)
+                .with_decimals(18)
+                .to_ui_string()
+                .unwrap_or_default(),
+            fee
+        );
+    }
+
+    let result = client.submit_intent(intent, options).await;
+    let receipt = match intent.src_domain {
+        Domain::Solana => result.context(
+            "Transaction failed, remember you need to create a token_account for the Escrow Program first",
+        )?,
+        Domain::Ethereum => result.context("Transaction failed")?,
+    };
+    match receipt {
+        EscrowReceipt::Solana {
+            signature,
+            jito_tip,
+            ..
+        } => {
+            println!("Transaction successful, signature: {}", signature);
+            print_jito_tip(client, jito_tip);
+        }
+        EscrowReceipt::Ethereum { intent_id, receipt } => {
+            println!(
+                "Transaction successful, receipt: {:?}",
+                receipt.transaction_hash
+            );
+            print_ethereum_intent_id(intent_id);
+        }
+    }
+
+    Ok(())
 }
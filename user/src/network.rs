@@ -0,0 +1,201 @@
+use std::env;
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::Cluster;
+use anyhow::{anyhow, bail, Result};
+use clap::builder::OsStr;
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString, IntoStaticStr};
+
+use crate::ethereum::ESCROW_CONTRACT_ADDRESS;
+use crate::solana::JITO_BLOCK_ENGINE_URL;
+use crate::AUCTIONEER_URL;
+
+/// Chain id of Ethereum mainnet.
+pub const MAINNET_CHAIN_ID: u64 = 1;
+/// Chain id of the Sepolia testnet.
+pub const SEPOLIA_CHAIN_ID: u64 = 11155111;
+/// Chain id of a local anvil or hardhat node.
+pub const LOCALNET_CHAIN_ID: u64 = 31337;
+
+/// Cross-domain fee in wei charged by the mainnet escrow contract, which does
+/// not quote it on chain.
+pub const MAINNET_CROSS_DOMAIN_FEE: u64 = 12_100_000_000_000_000;
+
+/// Named network profile selected with `--network`.
+///
+/// `devnet` and `sepolia` are the same profile: Solana devnet paired with
+/// the Sepolia testnet.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Serialize,
+    Deserialize,
+    EnumString,
+    Display,
+    IntoStaticStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum NetworkName {
+    #[default]
+    Mainnet,
+    #[serde(alias = "sepolia")]
+    #[strum(to_string = "devnet", serialize = "sepolia")]
+    Devnet,
+    Localnet,
+    Custom,
+}
+
+impl From<&NetworkName> for OsStr {
+    fn from(value: &NetworkName) -> Self {
+        let string: &'static str = value.into();
+        OsStr::from(string)
+    }
+}
+
+/// Endpoints and deployments of one network, on both domains.
+///
+/// Fields that have no known public value for a profile are `None` and must
+/// be set through the environment, see [`Network::with_env_overrides`].
+#[derive(Debug, Clone)]
+pub struct Network {
+    pub name: NetworkName,
+    pub solana_cluster: Cluster,
+    /// Expected Ethereum chain id, `None` to accept whatever the RPC reports.
+    pub ethereum_chain_id: Option<u64>,
+    pub escrow_contract: Option<Address>,
+    /// Cross-domain fee in wei paid when the escrow contract does not quote
+    /// one.
+    pub cross_domain_fee: Option<U256>,
+    pub bridge_escrow_program: Pubkey,
+    pub auctioneer_url: Option<String>,
+    /// Block engine URL, or comma separated URLs to race bundles through.
+    pub jito_block_engine_url: Option<String>,
+}
+
+impl Network {
+    pub fn new(name: NetworkName) -> Self {
+        match name {
+            NetworkName::Mainnet => Self {
+                name,
+                solana_cluster: Cluster::Mainnet,
+                ethereum_chain_id: Some(MAINNET_CHAIN_ID),
+                escrow_contract: Some(ESCROW_CONTRACT_ADDRESS.parse().unwrap()),
+                cross_domain_fee: Some(U256::from(MAINNET_CROSS_DOMAIN_FEE)),
+                bridge_escrow_program: bridge_escrow::ID,
+                auctioneer_url: Some(AUCTIONEER_URL.to_string()),
+                jito_block_engine_url: Some(JITO_BLOCK_ENGINE_URL.to_string()),
+            },
+            NetworkName::Devnet => Self {
+                name,
+                solana_cluster: Cluster::Devnet,
+                ethereum_chain_id: Some(SEPOLIA_CHAIN_ID),
+                escrow_contract: None,
+                cross_domain_fee: None,
+                bridge_escrow_program: bridge_escrow::ID,
+                auctioneer_url: None,
+                jito_block_engine_url: None,
+            },
+            NetworkName::Localnet => Self {
+                name,
+                solana_cluster: Cluster::Localnet,
+                ethereum_chain_id: Some(LOCALNET_CHAIN_ID),
+                escrow_contract: None,
+                cross_domain_fee: None,
+                bridge_escrow_program: bridge_escrow::ID,
+                auctioneer_url: None,
+                jito_block_engine_url: None,
+            },
+            NetworkName::Custom => Self {
+                name,
+                solana_cluster: Cluster::Mainnet,
+                ethereum_chain_id: None,
+                escrow_contract: None,
+                cross_domain_fee: None,
+                bridge_escrow_program: bridge_escrow::ID,
+                auctioneer_url: None,
+                jito_block_engine_url: None,
+            },
+        }
+    }
+
+    /// Override profile values with `SOLANA_RPC`, `ETHEREUM_CHAIN_ID`,
+    /// `ESCROW_CONTRACT_ADDRESS`, `BRIDGE_ESCROW_PROGRAM_ID`,
+    /// `AUCTIONEER_URL` and `JITO_BLOCK_ENGINE_URL` when they are set.
+    pub fn with_env_overrides(mut self) -> Result<Self> {
+        if let Ok(rpc_url) = env::var("SOLANA_RPC") {
+            self.solana_cluster = Cluster::from_str(&rpc_url)?;
+        }
+        if let Ok(chain_id) = env::var("ETHEREUM_CHAIN_ID") {
+            self.ethereum_chain_id = Some(
+                chain_id
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid ETHEREUM_CHAIN_ID {}: {}", chain_id, e))?,
+            );
+        }
+        if let Ok(escrow) = env::var("ESCROW_CONTRACT_ADDRESS") {
+            self.escrow_contract = Some(
+                escrow
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid ESCROW_CONTRACT_ADDRESS {}: {}", escrow, e))?,
+            );
+        }
+        if let Ok(program) = env::var("BRIDGE_ESCROW_PROGRAM_ID") {
+            self.bridge_escrow_program = Pubkey::from_str(&program)
+                .map_err(|e| anyhow!("Invalid BRIDGE_ESCROW_PROGRAM_ID {}: {}", program, e))?;
+        }
+        if let Ok(auctioneer_url) = env::var("AUCTIONEER_URL") {
+            self.auctioneer_url = Some(auctioneer_url);
+        }
+        if let Ok(block_engine_url) = env::var("JITO_BLOCK_ENGINE_URL") {
+            self.jito_block_engine_url = Some(block_engine_url);
+        }
+        Ok(self)
+    }
+
+    pub fn escrow_contract(&self) -> Result<Address> {
+        self.escrow_contract.ok_or_else(|| {
+            anyhow!(
+                "No Escrow contract is known for the {} network, set ESCROW_CONTRACT_ADDRESS",
+                self.name
+            )
+        })
+    }
+
+    pub fn auctioneer_url(&self) -> Result<&str> {
+        self.auctioneer_url.as_deref().ok_or_else(|| {
+            anyhow!(
+                "No auctioneer is known for the {} network, set AUCTIONEER_URL",
+                self.name
+            )
+        })
+    }
+
+    /// Check the chain id reported by the Ethereum RPC against the profile.
+    ///
+    /// Returns the chain id transactions should be signed with.
+    pub fn check_ethereum_chain_id(&self, rpc_chain_id: u64) -> Result<u64> {
+        match self.ethereum_chain_id {
+            Some(chain_id) if chain_id != rpc_chain_id => bail!(
+                "ETHEREUM_RPC is on chain {} but the {} network expects chain {}",
+                rpc_chain_id,
+                self.name,
+                chain_id
+            ),
+            _ => Ok(rpc_chain_id),
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self::new(NetworkName::Mainnet)
+    }
+}
@@ -0,0 +1,727 @@
+//! Air-gapped signing: an escrow transaction is built online, carried to
+//! the signing machine as a file, signed there and broadcast back online.
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signer::Signer;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anchor_spl::associated_token::get_associated_token_address;
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use bridge_escrow::instruction::EscrowAndStoreIntent;
+use ethers::signers::Signer as EthersSigner;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, NameOrAddress};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::compute_budget;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::sanitize::Sanitize;
+use solana_sdk::system_instruction::SystemInstruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+
+use crate::ethereum::{decode_escrow_funds, EscrowIntentInfo};
+use crate::intent::Domain;
+use crate::signer::{MantisEthereumSigner, MantisSolanaSigner};
+use crate::solana::{TxSendMethod, JITO_TIP_ACCOUNTS};
+
+/// Tag of the SPL token `SyncNative` instruction.
+const SYNC_NATIVE: u8 = 17;
+
+/// Tag of the associated token account `CreateIdempotent` instruction.
+const CREATE_IDEMPOTENT: u8 = 1;
+
+/// Escrow transaction exported by `build`, signed by `sign` and sent by
+/// `broadcast`.
+///
+/// The intent is included in readable form so it can be reviewed on the
+/// signing machine. [`OfflineTransaction::verify`] checks it against the
+/// transaction before signing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "domain", rename_all = "snake_case")]
+pub enum OfflineTransaction {
+    Solana {
+        intent_id: String,
+        /// `IntentPayload` stored by the bridge escrow program.
+        intent: Value,
+        /// `jito`, `jito_http` or `rpc`, decided when building as Jito needs a
+        /// tip.
+        tx_send_method: String,
+        /// Jito tip paid by the transaction, in lamports.
+        jito_tip: Option<u64>,
+        /// Durable nonce account the transaction advances, if any.
+        nonce_account: Option<String>,
+        /// Bincode serialized `Transaction`, base64 encoded.
+        transaction: String,
+    },
+    Ethereum {
+        /// `newIntentInfo` tuple passed to `escrowFunds`.
+        intent: Value,
+        chain_id: u64,
+        transaction: TypedTransaction,
+        /// RLP encoding of the unsigned transaction, hashed when signing.
+        unsigned_rlp: Bytes,
+        /// RLP encoding of the signed transaction, set by `sign`.
+        signed_rlp: Option<Bytes>,
+    },
+}
+
+impl OfflineTransaction {
+    pub fn solana(
+        payload: &bridge_escrow::IntentPayload,
+        tx_send_method: TxSendMethod,
+        jito_tip: Option<u64>,
+        nonce_account: Option<String>,
+        transaction: &Transaction,
+    ) -> Result<Self> {
+        Ok(Self::Solana {
+            intent_id: payload.intent_id.clone(),
+            intent: solana_intent_json(payload),
+            tx_send_method: tx_send_method.to_string(),
+            jito_tip,
+            nonce_account,
+            transaction: encode_solana_transaction(transaction)?,
+        })
+    }
+
+    pub fn ethereum(info: &EscrowIntentInfo, chain_id: u64, transaction: TypedTransaction) -> Self {
+        Self::Ethereum {
+            intent: ethereum_intent_json(info),
+            chain_id,
+            unsigned_rlp: transaction.rlp(chain_id),
+            transaction,
+            signed_rlp: None,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read transaction file {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| anyhow!("Invalid transaction file {}: {}", path.display(), e))
+    }
+
+    /// Write the transaction as pretty-printed JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to write transaction file {}: {}", path.display(), e))
+    }
+
+    pub fn domain(&self) -> Domain {
+        match self {
+            Self::Solana { .. } => Domain::Solana,
+            Self::Ethereum { .. } => Domain::Ethereum,
+        }
+    }
+
+    /// Whether every required signature is present.
+    pub fn is_signed(&self) -> Result<bool> {
+        Ok(match self {
+            Self::Solana { transaction, .. } => decode_solana_transaction(transaction)?.is_signed(),
+            Self::Ethereum { signed_rlp, .. } => signed_rlp.is_some(),
+        })
+    }
+
+    pub fn solana_transaction(&self) -> Result<Transaction> {
+        match self {
+            Self::Solana { transaction, .. } => decode_solana_transaction(transaction),
+            Self::Ethereum { .. } => bail!("Not a Solana transaction"),
+        }
+    }
+
+    pub fn tx_send_method(&self) -> Result<TxSendMethod> {
+        match self {
+            Self::Solana { tx_send_method, .. } => TxSendMethod::from_str(tx_send_method)
+                .map_err(|e| anyhow!("Invalid tx_send_method {}: {}", tx_send_method, e)),
+            Self::Ethereum { .. } => bail!("Not a Solana transaction"),
+        }
+    }
+
+    /// Check that the transaction does what its readable `intent` says and
+    /// return a summary of what signing it approves, to review before signing.
+    ///
+    /// The intent is decoded from the Solana escrow instruction or from the
+    /// Ethereum call data. Solana transactions may only hold the instructions
+    /// `build` adds. The unsigned RLP of an Ethereum transaction must encode
+    /// `transaction`, as it is what gets hashed and signed.
+    pub fn verify(&self) -> Result<Value> {
+        match self {
+            Self::Solana {
+                intent_id,
+                intent,
+                jito_tip,
+                nonce_account,
+                transaction,
+                ..
+            } => {
+                let tx = decode_solana_transaction(transaction)?;
+                let (program_id, payload) =
+                    escrowed_solana_intent(&tx, *jito_tip, nonce_account.as_deref())?;
+                let decoded = solana_intent_json(&payload);
+                if payload.intent_id != *intent_id || decoded != *intent {
+                    bail!(
+                        "The transaction escrows a different intent than the file shows: {}",
+                        decoded
+                    );
+                }
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                let expires_in_sec = seconds_until_timeout(payload.timeout_timestamp_in_sec, now)?;
+
+                Ok(json!({
+                    "intent": decoded,
+                    "expires_in_sec": expires_in_sec,
+                    "escrow_program": program_id.to_string(),
+                    "fee_payer": tx.message.account_keys.first().map(ToString::to_string),
+                    "jito_tip": jito_tip,
+                    "nonce_account": nonce_account,
+                }))
+            }
+            Self::Ethereum {
+                intent,
+                chain_id,
+                transaction,
+                unsigned_rlp,
+                ..
+            } => {
+                if transaction.rlp(*chain_id) != *unsigned_rlp {
+                    bail!("unsigned_rlp does not encode the transaction");
+                }
+                if let Some(tx_chain_id) = transaction.chain_id() {
+                    if tx_chain_id.as_u64() != *chain_id {
+                        bail!(
+                            "The transaction is for chain {}, not {}",
+                            tx_chain_id,
+                            chain_id
+                        );
+                    }
+                }
+                let data = transaction
+                    .data()
+                    .map(|data| data.as_ref())
+                    .unwrap_or_default();
+                let decoded = ethereum_intent_json(&decode_escrow_funds(data)?);
+                if decoded != *intent {
+                    bail!(
+                        "The transaction escrows a different intent than the file shows: {}",
+                        decoded
+                    );
+                }
+
+                Ok(json!({
+                    "intent": decoded,
+                    "chain_id": chain_id,
+                    "from": transaction.from(),
+                    "to": match transaction.to() {
+                        Some(NameOrAddress::Address(to)) => json!(to),
+                        Some(NameOrAddress::Name(name)) => json!(name),
+                        None => Value::Null,
+                    },
+                    "value": transaction.value().map(ToString::to_string),
+                }))
+            }
+        }
+    }
+
+    /// Add the signature of `signer` to a Solana transaction. Other signatures
+    /// are kept, so a transaction needing several keys can be signed in turns.
+    ///
+    /// Returns `false` when `signer` is not a signer of the transaction.
+    pub fn sign_solana(&mut self, signer: &MantisSolanaSigner) -> Result<bool> {
+        let Self::Solana { transaction, .. } = self else {
+            bail!("Not a Solana transaction");
+        };
+
+        let mut tx = decode_solana_transaction(transaction)?;
+        let pubkey = signer.pubkey();
+        let num_signers = tx.message.header.num_required_signatures as usize;
+        let signers = tx
+            .message
+            .account_keys
+            .get(..num_signers)
+            .ok_or_else(|| anyhow!("The transaction has fewer accounts than signers"))?;
+        if !signers.contains(&pubkey) {
+            return Ok(false);
+        }
+        let blockhash = tx.message.recent_blockhash;
+        tx.try_partial_sign(&[signer], blockhash)
+            .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
+
+        *transaction = encode_solana_transaction(&tx)?;
+        Ok(true)
+    }
+
+    /// Sign an Ethereum transaction with `signer`, which must be its sender.
+    pub async fn sign_ethereum(&mut self, signer: MantisEthereumSigner) -> Result<()> {
+        let Self::Ethereum {
+            chain_id,
+            transaction,
+            signed_rlp,
+            ..
+        } = self
+        else {
+            bail!("Not an Ethereum transaction");
+        };
+
+        let signer = signer.with_chain_id(*chain_id);
+        if transaction.from() != Some(&signer.address()) {
+            bail!(
+                "Transaction is sent from {:?}, not {:?}",
+                transaction.from(),
+                signer.address()
+            );
+        }
+        let signature = signer
+            .sign_transaction(transaction)
+            .await
+            .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
+
+        *signed_rlp = Some(transaction.rlp_signed(*chain_id, &signature));
+        Ok(())
+    }
+}
+
+fn solana_intent_json(payload: &bridge_escrow::IntentPayload) -> Value {
+    json!({
+        "intent_id": payload.intent_id,
+        "user_in": payload.user_in.to_string(),
+        "user_out": payload.user_out,
+        "token_in": payload.token_in.to_string(),
+        "amount_in": payload.amount_in,
+        "token_out": payload.token_out,
+        "amount_out": payload.amount_out,
+        "timeout_timestamp_in_sec": payload.timeout_timestamp_in_sec,
+        "single_domain": payload.single_domain,
+    })
+}
+
+fn ethereum_intent_json(info: &EscrowIntentInfo) -> Value {
+    json!({
+        "token_in": info.0,
+        "amount_in": info.1.to_string(),
+        "src_user": info.2,
+        "token_out": info.3,
+        "amount_out": info.4.to_string(),
+        "dst_user": info.5,
+        "winner_solver": info.6,
+        "timeout": info.7.to_string(),
+    })
+}
+
+/// Seconds left until the intent times out at `timeout_timestamp_in_sec`,
+/// failing once it has.
+fn seconds_until_timeout(timeout_timestamp_in_sec: u64, now: u64) -> Result<u64> {
+    match timeout_timestamp_in_sec.checked_sub(now) {
+        Some(left) if left > 0 => Ok(left),
+        _ => bail!(
+            "The intent timed out at {}, {} seconds ago",
+            timeout_timestamp_in_sec,
+            now - timeout_timestamp_in_sec
+        ),
+    }
+}
+
+/// Escrow program and intent of the single escrow instruction of `tx`,
+/// after checking the other instructions are the ones `build` adds: compute
+/// budget, advancing `nonce_account`, a Jito tip of `jito_tip` and wrapping
+/// `amount_in` SOL into the sender's WSOL account.
+fn escrowed_solana_intent(
+    tx: &Transaction,
+    jito_tip: Option<u64>,
+    nonce_account: Option<&str>,
+) -> Result<(Pubkey, bridge_escrow::IntentPayload)> {
+    let keys = &tx.message.account_keys;
+    let key = |index: u8| {
+        keys.get(index as usize)
+            .copied()
+            .ok_or_else(|| anyhow!("Invalid account index {}", index))
+    };
+    let account = |ix: &CompiledInstruction, position: usize| {
+        key(*ix
+            .accounts
+            .get(position)
+            .ok_or_else(|| anyhow!("Instruction is missing account {}", position))?)
+    };
+
+    let mut escrow = None;
+    for ix in &tx.message.instructions {
+        if ix.data.starts_with(&EscrowAndStoreIntent::DISCRIMINATOR) {
+            if escrow.is_some() {
+                bail!("The transaction escrows more than one intent");
+            }
+            let payload = EscrowAndStoreIntent::try_from_slice(&ix.data[8..])
+                .map_err(|e| anyhow!("Invalid escrow instruction: {}", e))?
+                .new_intent;
+            escrow = Some((key(ix.program_id_index)?, payload));
+        }
+    }
+    let (program_id, payload) = escrow.ok_or_else(|| anyhow!("No escrow instruction found"))?;
+    let wsol_account = get_associated_token_address(&payload.user_in, &spl_token::native_mint::ID);
+
+    for ix in &tx.message.instructions {
+        let ix_program = key(ix.program_id_index)?;
+        if (ix_program == program_id && ix.data.starts_with(&EscrowAndStoreIntent::DISCRIMINATOR))
+            || ix_program == compute_budget::ID
+        {
+            continue;
+        }
+        if ix_program == system_program::ID {
+            let instruction: SystemInstruction = bincode::deserialize(&ix.data)
+                .map_err(|e| anyhow!("Invalid system instruction: {}", e))?;
+            match instruction {
+                SystemInstruction::AdvanceNonceAccount
+                    if nonce_account == Some(account(ix, 0)?.to_string().as_str()) => {}
+                SystemInstruction::Transfer { lamports }
+                    if JITO_TIP_ACCOUNTS.contains(&account(ix, 1)?)
+                        && Some(lamports) == jito_tip => {}
+                SystemInstruction::Transfer { lamports }
+                    if account(ix, 0)? == payload.user_in
+                        && account(ix, 1)? == wsol_account
+                        && lamports == payload.amount_in => {}
+                instruction => bail!("Unexpected system instruction {:?}", instruction),
+            }
+        } else if ix_program == spl_associated_token_account::ID {
+            if ix.data != [CREATE_IDEMPOTENT] || account(ix, 1)? != wsol_account {
+                bail!("Unexpected token account creation");
+            }
+        } else if ix_program == spl_token::ID {
+            if ix.data != [SYNC_NATIVE] || account(ix, 0)? != wsol_account {
+                bail!("Unexpected token instruction");
+            }
+        } else {
+            bail!("Unexpected instruction of program {}", ix_program);
+        }
+    }
+
+    Ok((program_id, payload))
+}
+
+fn encode_solana_transaction(transaction: &Transaction) -> Result<String> {
+    Ok(BASE64.encode(bincode::serialize(transaction)?))
+}
+
+fn decode_solana_transaction(transaction: &str) -> Result<Transaction> {
+    let bytes = BASE64
+        .decode(transaction.trim())
+        .map_err(|e| anyhow!("Invalid base64 transaction: {}", e))?;
+    let transaction: Transaction =
+        bincode::deserialize(&bytes).map_err(|e| anyhow!("Invalid Solana transaction: {}", e))?;
+    transaction
+        .sanitize()
+        .map_err(|e| anyhow!("Invalid Solana transaction: {}", e))?;
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_client::solana_sdk::signature::Keypair;
+    use anchor_lang::InstructionData;
+    use ethers::abi::AbiEncode;
+    use ethers::signers::LocalWallet;
+    use ethers::types::{Address, Eip1559TransactionRequest, U256};
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::system_instruction;
+
+    use crate::ethereum::EscrowFundsCall;
+    use crate::keys::ethereum_wallet_from_hex;
+
+    const JITO_TIP: u64 = 10_000;
+    /// 2100-01-01, so test intents never time out.
+    const TIMEOUT: u64 = 4_102_444_800;
+    const CHAIN_ID: u64 = 1;
+    /// Keys of the first two Hardhat development accounts.
+    const SENDER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const OTHER_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+
+    fn hardhat_wallet(key: &str) -> LocalWallet {
+        ethereum_wallet_from_hex(key).unwrap()
+    }
+
+    fn payload(user_in: Pubkey) -> bridge_escrow::IntentPayload {
+        bridge_escrow::IntentPayload {
+            intent_id: "a1b2c3".to_string(),
+            user_in,
+            user_out: "0x742d35Cc6634C0532925a3b844Bc454e4438f44e".to_string(),
+            token_in: spl_token::native_mint::ID,
+            amount_in: 50,
+            token_out: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+            amount_out: "49".to_string(),
+            timeout_timestamp_in_sec: TIMEOUT,
+            single_domain: false,
+        }
+    }
+
+    /// Escrow transaction shaped like the ones `build` exports, with
+    /// `extra` instructions appended.
+    fn solana_transaction(owner: &Keypair, extra: Vec<Instruction>) -> Transaction {
+        let user = owner.pubkey();
+        let wsol_account = get_associated_token_address(&user, &spl_token::native_mint::ID);
+        let mut instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            system_instruction::transfer(&user, &JITO_TIP_ACCOUNTS[0], JITO_TIP),
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &user,
+                &user,
+                &spl_token::native_mint::ID,
+                &spl_token::ID,
+            ),
+            system_instruction::transfer(&user, &wsol_account, 50),
+            spl_token::instruction::sync_native(&spl_token::ID, &wsol_account).unwrap(),
+            Instruction {
+                program_id: bridge_escrow::ID,
+                accounts: vec![
+                    AccountMeta::new(user, true),
+                    AccountMeta::new(Pubkey::new_unique(), false),
+                ],
+                data: bridge_escrow::instruction::EscrowAndStoreIntent {
+                    new_intent: payload(user),
+                }
+                .data(),
+            },
+        ];
+        instructions.extend(extra);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&user));
+        transaction.message.recent_blockhash = Hash::new_unique();
+        transaction
+    }
+
+    fn offline_solana(owner: &Keypair, extra: Vec<Instruction>) -> OfflineTransaction {
+        OfflineTransaction::solana(
+            &payload(owner.pubkey()),
+            TxSendMethod::JITO_HTTP,
+            Some(JITO_TIP),
+            None,
+            &solana_transaction(owner, extra),
+        )
+        .unwrap()
+    }
+
+    /// Save `transaction` to a file and load it back, as between machines.
+    fn carry(transaction: &OfflineTransaction, name: &str) -> OfflineTransaction {
+        let path = std::env::temp_dir().join(format!(
+            "mantis-offline-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        transaction.save(&path).unwrap();
+        let loaded = OfflineTransaction::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn solana_sign_round_trip() {
+        let owner = Keypair::new();
+        let built = offline_solana(&owner, Vec::new());
+        assert!(!built.is_signed().unwrap());
+
+        let mut transaction = carry(&built, "solana-built");
+        let summary = transaction.verify().unwrap();
+        assert_eq!(
+            summary["intent"],
+            solana_intent_json(&payload(owner.pubkey()))
+        );
+        assert_eq!(summary["jito_tip"], JITO_TIP);
+        assert!(summary["expires_in_sec"].as_u64().unwrap() > 0);
+        assert!(!transaction
+            .sign_solana(&MantisSolanaSigner::from(Keypair::new()))
+            .unwrap());
+        assert!(transaction
+            .sign_solana(&MantisSolanaSigner::from(owner.insecure_clone()))
+            .unwrap());
+
+        let signed = carry(&transaction, "solana-signed");
+        assert!(signed.is_signed().unwrap());
+        signed.verify().unwrap();
+        signed.solana_transaction().unwrap().verify().unwrap();
+        assert!(matches!(
+            signed.tx_send_method().unwrap(),
+            TxSendMethod::JITO_HTTP
+        ));
+    }
+
+    #[test]
+    fn solana_intent_must_match_the_transaction() {
+        let owner = Keypair::new();
+        let mut transaction = offline_solana(&owner, Vec::new());
+        if let OfflineTransaction::Solana { intent, .. } = &mut transaction {
+            intent["amount_out"] = json!("1");
+        }
+        let err = transaction.verify().unwrap_err();
+        assert!(err.to_string().contains("different intent"), "{}", err);
+    }
+
+    #[test]
+    fn solana_jito_tip_must_match_the_transaction() {
+        let owner = Keypair::new();
+        let mut transaction = offline_solana(&owner, Vec::new());
+        if let OfflineTransaction::Solana { jito_tip, .. } = &mut transaction {
+            *jito_tip = Some(JITO_TIP + 1);
+        }
+        assert!(transaction.verify().is_err());
+    }
+
+    #[test]
+    fn solana_extra_instructions_are_rejected() {
+        let owner = Keypair::new();
+        let drain = system_instruction::transfer(&owner.pubkey(), &Pubkey::new_unique(), 1);
+        assert!(offline_solana(&owner, vec![drain]).verify().is_err());
+
+        let unknown = Instruction::new_with_bytes(Pubkey::new_unique(), &[], Vec::new());
+        assert!(offline_solana(&owner, vec![unknown]).verify().is_err());
+    }
+
+    #[test]
+    fn solana_wsol_transfer_must_match_amount_in() {
+        let owner = Keypair::new();
+        let wsol_account =
+            get_associated_token_address(&owner.pubkey(), &spl_token::native_mint::ID);
+        let wrap = system_instruction::transfer(&owner.pubkey(), &wsol_account, 1);
+        let err = offline_solana(&owner, vec![wrap]).verify().unwrap_err();
+        assert!(
+            err.to_string().contains("Unexpected system instruction"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn malformed_solana_transactions_are_rejected() {
+        let owner = Keypair::new();
+        let mut tx = solana_transaction(&owner, Vec::new());
+        tx.message.header.num_required_signatures = u8::MAX;
+        let mut transaction = OfflineTransaction::solana(
+            &payload(owner.pubkey()),
+            TxSendMethod::JITO_HTTP,
+            Some(JITO_TIP),
+            None,
+            &tx,
+        )
+        .unwrap();
+
+        assert!(transaction.verify().is_err());
+        let err = transaction
+            .sign_solana(&MantisSolanaSigner::from(owner))
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid Solana transaction"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn timed_out_solana_intents_are_rejected() {
+        assert_eq!(seconds_until_timeout(TIMEOUT, TIMEOUT - 60).unwrap(), 60);
+        assert!(seconds_until_timeout(TIMEOUT, TIMEOUT).is_err());
+        let err = seconds_until_timeout(TIMEOUT, TIMEOUT + 60).unwrap_err();
+        assert!(err.to_string().contains("60 seconds ago"), "{}", err);
+    }
+
+    fn intent_info(src_user: Address) -> EscrowIntentInfo {
+        (
+            Address::random(),
+            U256::from(1_000_000),
+            src_user,
+            spl_token::native_mint::ID.to_string(),
+            U256::from(5_000_000),
+            Pubkey::new_unique().to_string(),
+            String::new(),
+            U256::from(3_600),
+        )
+    }
+
+    fn ethereum_transaction(from: Address, info: &EscrowIntentInfo) -> TypedTransaction {
+        let call = EscrowFundsCall {
+            new_intent_info: info.clone(),
+        };
+        Eip1559TransactionRequest::new()
+            .from(from)
+            .to(Address::random())
+            .value(U256::from(1_000))
+            .data(call.encode())
+            .nonce(7)
+            .gas(200_000)
+            .max_fee_per_gas(30_000_000_000u64)
+            .max_priority_fee_per_gas(1_000_000_000u64)
+            .chain_id(CHAIN_ID)
+            .into()
+    }
+
+    #[tokio::test]
+    async fn ethereum_sign_round_trip() {
+        let wallet = hardhat_wallet(SENDER_KEY);
+        let info = intent_info(wallet.address());
+        let tx = ethereum_transaction(wallet.address(), &info);
+        let built = OfflineTransaction::ethereum(&info, CHAIN_ID, tx.clone());
+
+        let mut transaction = carry(&built, "ethereum-built");
+        let summary = transaction.verify().unwrap();
+        assert_eq!(summary["intent"], ethereum_intent_json(&info));
+        assert_eq!(summary["chain_id"], CHAIN_ID);
+        assert!(transaction
+            .sign_ethereum(hardhat_wallet(OTHER_KEY).into())
+            .await
+            .is_err());
+        transaction
+            .sign_ethereum(wallet.clone().into())
+            .await
+            .unwrap();
+
+        let signed = carry(&transaction, "ethereum-signed");
+        assert!(signed.is_signed().unwrap());
+        signed.verify().unwrap();
+        let signature = wallet
+            .with_chain_id(CHAIN_ID)
+            .sign_transaction(&tx)
+            .await
+            .unwrap();
+        let OfflineTransaction::Ethereum { signed_rlp, .. } = signed else {
+            panic!("Not an Ethereum transaction");
+        };
+        assert_eq!(signed_rlp, Some(tx.rlp_signed(CHAIN_ID, &signature)));
+    }
+
+    #[test]
+    fn ethereum_intent_must_match_the_call_data() {
+        let wallet = hardhat_wallet(SENDER_KEY);
+        let info = intent_info(wallet.address());
+        let tx = ethereum_transaction(wallet.address(), &info);
+        let mut transaction = OfflineTransaction::ethereum(&info, CHAIN_ID, tx);
+        if let OfflineTransaction::Ethereum { intent, .. } = &mut transaction {
+            intent["amount_in"] = json!("1");
+        }
+        let err = transaction.verify().unwrap_err();
+        assert!(err.to_string().contains("different intent"), "{}", err);
+    }
+
+    #[test]
+    fn ethereum_unsigned_rlp_must_encode_the_transaction() {
+        let wallet = hardhat_wallet(SENDER_KEY);
+        let info = intent_info(wallet.address());
+        let tx = ethereum_transaction(wallet.address(), &info);
+
+        let mut transaction = OfflineTransaction::ethereum(&info, CHAIN_ID, tx.clone());
+        if let OfflineTransaction::Ethereum { unsigned_rlp, .. } = &mut transaction {
+            let mut other = tx.clone();
+            other.set_nonce(8);
+            *unsigned_rlp = other.rlp(CHAIN_ID);
+        }
+        let err = transaction.verify().unwrap_err();
+        assert!(err.to_string().contains("unsigned_rlp"), "{}", err);
+
+        // A file for another chain than the transaction's.
+        let transaction = OfflineTransaction::ethereum(&info, CHAIN_ID + 1, tx);
+        assert!(transaction.verify().is_err());
+    }
+}
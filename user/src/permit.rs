@@ -0,0 +1,313 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use ethers::abi::{self, Token};
+use ethers::prelude::*;
+use ethers::types::transaction::eip712::{EIP712Domain, Eip712};
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::ethereum::EthereumSigner;
+
+abigen!(
+    ERC20Permit,
+    r#"[
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "name",
+        "outputs": [{ "name": "", "type": "string" }],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "version",
+        "outputs": [{ "name": "", "type": "string" }],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [{ "name": "owner", "type": "address" }],
+        "name": "nonces",
+        "outputs": [{ "name": "", "type": "uint256" }],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "DOMAIN_SEPARATOR",
+        "outputs": [{ "name": "", "type": "bytes32" }],
+        "stateMutability": "view",
+        "type": "function"
+    }]"#
+);
+
+abigen!(
+    Permit2,
+    r#"[
+    {
+        "constant": true,
+        "inputs": [],
+        "name": "DOMAIN_SEPARATOR",
+        "outputs": [{ "name": "", "type": "bytes32" }],
+        "stateMutability": "view",
+        "type": "function"
+    }]"#
+);
+
+/// Uniswap Permit2, deployed at the same address on every chain.
+pub const PERMIT2_ADDRESS: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+/// How long a signed permit stays valid.
+pub const PERMIT_VALIDITY_SECS: u64 = 30 * 60;
+
+const EIP2612_PERMIT_TYPE: &str =
+    "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+const PERMIT2_TRANSFER_FROM_TYPE: &str = "PermitTransferFrom(TokenPermissions permitted,address spender,uint256 nonce,uint256 deadline)TokenPermissions(address token,uint256 amount)";
+const PERMIT2_TOKEN_PERMISSIONS_TYPE: &str = "TokenPermissions(address token,uint256 amount)";
+
+/// Gasless approval scheme used instead of a separate `approve` transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PermitMethod {
+    /// The token's own `permit` (EIP-2612).
+    Eip2612,
+    /// A Permit2 signature transfer, the token must be approved to Permit2 once.
+    Permit2,
+}
+
+/// Permit signature, passed to a contract instead of an ERC20 allowance.
+#[derive(Debug, Clone)]
+pub enum SignedPermit {
+    Eip2612 {
+        deadline: U256,
+        v: u8,
+        r: [u8; 32],
+        s: [u8; 32],
+    },
+    Permit2 {
+        nonce: U256,
+        deadline: U256,
+        signature: Bytes,
+    },
+}
+
+/// Version of the EIP-2612 domain of tokens that do not expose `version()`.
+const DEFAULT_PERMIT_VERSION: &str = "1";
+
+/// EIP-2612 `Permit` message.
+///
+/// The domain separator is read from the token and signed as is: local
+/// signers and remote signers, which are sent the `0x1901 || domain_separator
+/// || struct_hash` preimage, both use it. The domain name and version only
+/// fill in [`Eip712::domain`].
+#[derive(Debug, Clone)]
+struct Eip2612Permit {
+    token: Address,
+    token_name: String,
+    token_version: String,
+    chain_id: u64,
+    domain_separator: [u8; 32],
+    owner: Address,
+    spender: Address,
+    value: U256,
+    nonce: U256,
+    deadline: U256,
+}
+
+impl Eip712 for Eip2612Permit {
+    type Error = Infallible;
+
+    fn domain_separator(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(self.domain_separator)
+    }
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: self.token_name.clone(),
+            version: self.token_version.clone(),
+            chain_id: self.chain_id.into(),
+            verifying_contract: self.token,
+            salt: None,
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(EIP2612_PERMIT_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(abi::encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::Address(self.owner),
+            Token::Address(self.spender),
+            Token::Uint(self.value),
+            Token::Uint(self.nonce),
+            Token::Uint(self.deadline),
+        ])))
+    }
+}
+
+/// Permit2 `PermitTransferFrom` message, letting `spender` pull `amount`
+/// of `token` once.
+#[derive(Debug, Clone)]
+struct Permit2TransferFrom {
+    chain_id: u64,
+    domain_separator: [u8; 32],
+    token: Address,
+    amount: U256,
+    spender: Address,
+    nonce: U256,
+    deadline: U256,
+}
+
+impl Eip712 for Permit2TransferFrom {
+    type Error = Infallible;
+
+    fn domain_separator(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(self.domain_separator)
+    }
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: "Permit2".to_string(),
+            version: String::new(),
+            chain_id: self.chain_id.into(),
+            verifying_contract: PERMIT2_ADDRESS.parse().unwrap(),
+            salt: None,
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(PERMIT2_TRANSFER_FROM_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let token_permissions = keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256(PERMIT2_TOKEN_PERMISSIONS_TYPE).to_vec()),
+            Token::Address(self.token),
+            Token::Uint(self.amount),
+        ]));
+
+        Ok(keccak256(abi::encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::FixedBytes(token_permissions.to_vec()),
+            Token::Address(self.spender),
+            Token::Uint(self.nonce),
+            Token::Uint(self.deadline),
+        ])))
+    }
+}
+
+/// Sign a permit letting `spender` pull `amount` of `token` from the signer.
+///
+/// Returns `None` when the token cannot be used with `method`: it does not
+/// implement EIP-2612, or it has not been approved to Permit2 yet. Callers
+/// then fall back to a classic `approve`.
+pub async fn sign_permit(
+    wallet: &Arc<EthereumSigner>,
+    method: PermitMethod,
+    token: Address,
+    spender: Address,
+    amount: U256,
+) -> Result<Option<SignedPermit>> {
+    let deadline =
+        U256::from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + PERMIT_VALIDITY_SECS);
+    let chain_id = wallet.signer().chain_id();
+
+    match method {
+        PermitMethod::Eip2612 => {
+            let erc20 = ERC20Permit::new(token, wallet.clone());
+            let owner = wallet.address();
+            // Tokens without EIP-2612 revert on either call.
+            let (nonce, domain_separator) = match (
+                erc20.nonces(owner).call().await,
+                erc20.domain_separator().call().await,
+            ) {
+                (Ok(nonce), Ok(domain_separator)) => (nonce, domain_separator),
+                _ => return Ok(None),
+            };
+            let token_name = erc20.name().call().await.unwrap_or_default();
+            let token_version = erc20
+                .version()
+                .call()
+                .await
+                .unwrap_or_else(|_| DEFAULT_PERMIT_VERSION.to_string());
+
+            let permit = Eip2612Permit {
+                token,
+                token_name,
+                token_version,
+                chain_id,
+                domain_separator,
+                owner,
+                spender,
+                value: amount,
+                nonce,
+                deadline,
+            };
+            let signature = wallet
+                .signer()
+                .sign_typed_data(&permit)
+                .await
+                .map_err(|e| anyhow!("Failed to sign EIP-2612 permit: {}", e))?;
+
+            let mut r = [0u8; 32];
+            let mut s = [0u8; 32];
+            signature.r.to_big_endian(&mut r);
+            signature.s.to_big_endian(&mut s);
+
+            Ok(Some(SignedPermit::Eip2612 {
+                deadline,
+                v: signature.v as u8,
+                r,
+                s,
+            }))
+        }
+        PermitMethod::Permit2 => {
+            let permit2_address = PERMIT2_ADDRESS.parse::<Address>()?;
+            let erc20 = crate::ethereum::ERC20::new(token, wallet.clone());
+            let allowance = erc20
+                .allowance(wallet.address(), permit2_address)
+                .call()
+                .await?;
+            if allowance < amount {
+                return Ok(None);
+            }
+
+            let permit2 = Permit2::new(permit2_address, wallet.clone());
+            let domain_separator = permit2.domain_separator().call().await?;
+            // Permit2 signature transfers use unordered nonces.
+            let nonce = U256::from(rand::random::<u128>());
+
+            let permit = Permit2TransferFrom {
+                chain_id,
+                domain_separator,
+                token,
+                amount,
+                spender,
+                nonce,
+                deadline,
+            };
+            let signature = wallet
+                .signer()
+                .sign_typed_data(&permit)
+                .await
+                .map_err(|e| anyhow!("Failed to sign Permit2 transfer: {}", e))?;
+
+            Ok(Some(SignedPermit::Permit2 {
+                nonce,
+                deadline,
+                signature: signature.to_vec().into(),
+            }))
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//! Signers of both domains, holding a key in process or delegating to a
+//! remote signing service.
+//!
+//! [`MantisSolanaSigner`] implements the Solana [`Signer`] trait and
+//! [`MantisEthereumSigner`] the ethers [`EthersSigner`] trait, so either can
+//! be used wherever a `Keypair` or `LocalWallet` was, including anchor's
+//! `Client`, [`crate::solana::submit`] and `SignerMiddleware`.
+//!
+//! Remote signers speak two small HTTP protocols:
+//!
+//! * Solana: `POST {url}` with `{"pubkey": "<base58>", "message": "<base58>"}`,
+//!   answered with `{"signature": "<base58>"}`.
+//! * Ethereum: Web3Signer's `POST {url}/api/v1/eth1/sign/{address}` with
+//!   `{"data": "0x..."}`, answered with the hex signature of
+//!   `keccak256(data)`.
+//!
+//! Every signature returned by a remote signer is verified against the
+//! configured key before it is used.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signature};
+use anchor_client::solana_sdk::signer::{Signer, SignerError};
+use async_trait::async_trait;
+use ethers::signers::{LocalWallet, Signer as EthersSigner, WalletError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, RecoveryMessage, Signature as EthereumSignature, H256};
+use ethers::utils::{hex, keccak256};
+use serde::{Deserialize, Serialize};
+use solana_sdk::bs58;
+use tokio::runtime::RuntimeFlavor;
+
+/// Solana signer with its key in process or behind a remote signer.
+#[derive(Debug)]
+pub enum MantisSolanaSigner {
+    Local(Arc<Keypair>),
+    Remote(RemoteSolanaSigner),
+}
+
+impl MantisSolanaSigner {
+    /// The key in process, `None` for remote signers.
+    pub fn keypair(&self) -> Option<Arc<Keypair>> {
+        match self {
+            Self::Local(keypair) => Some(keypair.clone()),
+            Self::Remote(_) => None,
+        }
+    }
+}
+
+impl From<Keypair> for MantisSolanaSigner {
+    fn from(keypair: Keypair) -> Self {
+        Self::Local(Arc::new(keypair))
+    }
+}
+
+impl From<RemoteSolanaSigner> for MantisSolanaSigner {
+    fn from(remote: RemoteSolanaSigner) -> Self {
+        Self::Remote(remote)
+    }
+}
+
+impl Signer for MantisSolanaSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        match self {
+            Self::Local(keypair) => keypair.try_pubkey(),
+            Self::Remote(remote) => Ok(remote.pubkey),
+        }
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        match self {
+            Self::Local(keypair) => keypair.try_sign_message(message),
+            Self::Remote(remote) => remote.sign(message),
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Solana key held by a remote signer reachable at `url`.
+#[derive(Debug)]
+pub struct RemoteSolanaSigner {
+    url: String,
+    pubkey: Pubkey,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct SolanaSignRequest {
+    pubkey: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct SolanaSignResponse {
+    signature: String,
+}
+
+impl RemoteSolanaSigner {
+    pub fn new(url: impl Into<String>, pubkey: Pubkey) -> Self {
+        Self {
+            url: url.into(),
+            pubkey,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sign `message`, blocking the current thread until the signer answers.
+    ///
+    /// The Solana `Signer` trait is synchronous, so inside a tokio runtime
+    /// this needs the multi-threaded runtime `#[tokio::main]` creates and
+    /// fails on a current-thread one.
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let request = SolanaSignRequest {
+            pubkey: self.pubkey.to_string(),
+            message: bs58::encode(message).into_string(),
+        };
+        let response = block_on(async {
+            self.client
+                .post(&self.url)
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<SolanaSignResponse>()
+                .await
+        })?
+        .map_err(|e| SignerError::Connection(format!("Remote signer {}: {}", self.url, e)))?;
+
+        let signature: Signature = response.signature.parse().map_err(|e| {
+            SignerError::Custom(format!(
+                "Remote signer {} sent an invalid signature: {}",
+                self.url, e
+            ))
+        })?;
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Custom(format!(
+                "Remote signer {} did not sign with {}",
+                self.url, self.pubkey
+            )));
+        }
+        Ok(signature)
+    }
+}
+
+/// Run `future` to completion from synchronous code, outside a tokio runtime
+/// or inside a multi-threaded one.
+fn block_on<F: Future>(future: F) -> Result<F::Output, SignerError> {
+    match tokio::runtime::Handle::try_current() {
+        // `block_in_place` panics on a current-thread runtime.
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::CurrentThread => {
+            Err(SignerError::Custom(
+                "Remote Solana signers need a multi-threaded tokio runtime".to_string(),
+            ))
+        }
+        Ok(handle) => Ok(tokio::task::block_in_place(|| handle.block_on(future))),
+        Err(_) => {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| SignerError::Custom(format!("Failed to start a runtime: {}", e)))?;
+            Ok(runtime.block_on(future))
+        }
+    }
+}
+
+/// Ethereum signer with its key in process or behind a Web3Signer.
+#[derive(Debug)]
+pub enum MantisEthereumSigner {
+    Local(LocalWallet),
+    Remote(RemoteEthereumSigner),
+}
+
+impl From<LocalWallet> for MantisEthereumSigner {
+    fn from(wallet: LocalWallet) -> Self {
+        Self::Local(wallet)
+    }
+}
+
+impl From<RemoteEthereumSigner> for MantisEthereumSigner {
+    fn from(remote: RemoteEthereumSigner) -> Self {
+        Self::Remote(remote)
+    }
+}
+
+/// Error of a [`MantisEthereumSigner`].
+#[derive(Debug)]
+pub enum EthereumSignerError {
+    Local(WalletError),
+    Remote(String),
+}
+
+impl fmt::Display for EthereumSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(e) => write!(f, "{}", e),
+            Self::Remote(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for EthereumSignerError {}
+
+#[async_trait]
+impl EthersSigner for MantisEthereumSigner {
+    type Error = EthereumSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<EthereumSignature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_message(message)
+                .await
+                .map_err(EthereumSignerError::Local),
+            Self::Remote(remote) => {
+                // EIP-191 personal message, hashed by the signer.
+                let message = message.as_ref();
+                let mut data =
+                    format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+                data.extend_from_slice(message);
+                remote.sign(&data, None).await
+            }
+        }
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+    ) -> Result<EthereumSignature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_transaction(tx)
+                .await
+                .map_err(EthereumSignerError::Local),
+            Self::Remote(remote) => {
+                let chain_id = tx
+                    .chain_id()
+                    .map(|id| id.as_u64())
+                    .unwrap_or(remote.chain_id);
+                remote.sign(tx.rlp(chain_id).as_ref(), Some(chain_id)).await
+            }
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<EthereumSignature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_typed_data(payload)
+                .await
+                .map_err(EthereumSignerError::Local),
+            Self::Remote(remote) => {
+                let domain_separator = payload
+                    .domain_separator()
+                    .map_err(|e| EthereumSignerError::Remote(e.to_string()))?;
+                let struct_hash = payload
+                    .struct_hash()
+                    .map_err(|e| EthereumSignerError::Remote(e.to_string()))?;
+                let mut data = vec![0x19, 0x01];
+                data.extend_from_slice(&domain_separator);
+                data.extend_from_slice(&struct_hash);
+                remote.sign(&data, None).await
+            }
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(wallet) => wallet.address(),
+            Self::Remote(remote) => remote.address,
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(wallet) => wallet.chain_id(),
+            Self::Remote(remote) => remote.chain_id,
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(wallet) => Self::Local(wallet.with_chain_id(chain_id)),
+            Self::Remote(remote) => Self::Remote(RemoteEthereumSigner {
+                chain_id: chain_id.into(),
+                ..remote
+            }),
+        }
+    }
+}
+
+/// Ethereum key held by a Web3Signer reachable at `url`.
+#[derive(Debug, Clone)]
+pub struct RemoteEthereumSigner {
+    url: String,
+    address: Address,
+    chain_id: u64,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct EthereumSignRequest {
+    data: String,
+}
+
+impl RemoteEthereumSigner {
+    pub fn new(url: impl Into<String>, address: Address) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            address,
+            chain_id: 1,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Have the signer sign `keccak256(data)`.
+    ///
+    /// With `eip155_chain_id` the recovery id is encoded as EIP-155 `v`, as
+    /// for transactions, otherwise as 27 or 28.
+    async fn sign(
+        &self,
+        data: &[u8],
+        eip155_chain_id: Option<u64>,
+    ) -> Result<EthereumSignature, EthereumSignerError> {
+        let url = format!("{}/api/v1/eth1/sign/{:?}", self.url, self.address);
+        let request = EthereumSignRequest {
+            data: format!("0x{}", hex::encode(data)),
+        };
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| EthereumSignerError::Remote(format!("Remote signer {}: {}", url, e)))?
+            .text()
+            .await
+            .map_err(|e| EthereumSignerError::Remote(format!("Remote signer {}: {}", url, e)))?;
+
+        let invalid = |e: String| {
+            EthereumSignerError::Remote(format!(
+                "Remote signer {} sent an invalid signature: {}",
+                url, e
+            ))
+        };
+        let bytes = hex::decode(response.trim().trim_matches('"').trim_start_matches("0x"))
+            .map_err(|e| invalid(e.to_string()))?;
+        let mut signature =
+            EthereumSignature::try_from(bytes.as_slice()).map_err(|e| invalid(e.to_string()))?;
+        let recovery_id = match signature.v {
+            0 | 1 => signature.v,
+            27 | 28 => signature.v - 27,
+            v => return Err(invalid(format!("unexpected v {}", v))),
+        };
+
+        signature.v = recovery_id + 27;
+        let signer = signature
+            .recover(RecoveryMessage::Hash(H256::from(keccak256(data))))
+            .map_err(|e| invalid(e.to_string()))?;
+        if signer != self.address {
+            return Err(EthereumSignerError::Remote(format!(
+                "Remote signer {} signed with {:?} instead of {:?}",
+                url, signer, self.address
+            )));
+        }
+
+        if let Some(chain_id) = eip155_chain_id {
+            signature.v = recovery_id + 35 + chain_id * 2;
+        }
+        Ok(signature)
+    }
+}
@@ -1,47 +1,111 @@
-use crate::env;
-use crate::CommitmentConfig;
+use crate::generate_random_intent_id;
+use crate::intent::IntentRequest;
+use crate::jito::{
+    fetch_tip_floor, simulate_bundle, JitoBlockEngines, JITO_TIP_FLOOR_URL, TIP_FLOOR_PERCENTILES,
+};
+use crate::network::Network;
+use crate::signer::MantisSolanaSigner;
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use anchor_client::solana_sdk::signature::{Keypair, Signer};
 use anchor_client::Client;
 use anchor_lang::system_program;
+use anchor_lang::{AnchorDeserialize, InstructionData, ToAccountMetas};
 use anchor_spl::associated_token;
 use anchor_spl::associated_token::get_associated_token_address;
-use anyhow::{anyhow, Result};
-use jito_protos::searcher::SubscribeBundleResultsRequest;
+use anyhow::{anyhow, bail, Result};
+use clap::builder::OsStr;
+use rand::seq::SliceRandom;
+use solana_client::nonce_utils::nonblocking;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_sdk::clock::Clock;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::pubkey;
 use solana_sdk::signature::Signature;
 use solana_sdk::system_instruction;
+use solana_sdk::sysvar;
 use spl_associated_token_account::instruction;
 use spl_token::instruction::sync_native;
+use spl_token::solana_program::program_pack::Pack;
+use spl_token::state::Mint;
+use std::fmt;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
-use clap::builder::OsStr;
-use solana_client::rpc_config::RpcSendTransactionConfig;
-use tokio::time::sleep;
-use tokio::time::Duration;
 use strum::EnumString;
 use strum_macros::{Display, IntoStaticStr};
+use tokio::time::sleep;
+use tokio::time::Duration;
 use {
     solana_client::nonblocking::rpc_client::RpcClient,
-    solana_sdk::{hash::Hash, instruction::Instruction, transaction::Transaction},
+    solana_sdk::{
+        hash::Hash,
+        instruction::{Instruction, InstructionError},
+        message::Message,
+        transaction::{Transaction, TransactionError},
+    },
 };
 
 pub const JITO_ADDRESS: Pubkey =
     solana_program::pubkey!("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5");
+/// Tip accounts of the Jito tip payment program. Each bundle tips one of
+/// them at random, so concurrent bundles do not contend for one account.
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    JITO_ADDRESS,
+    solana_program::pubkey!("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    solana_program::pubkey!("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    solana_program::pubkey!("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    solana_program::pubkey!("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    solana_program::pubkey!("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    solana_program::pubkey!("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    solana_program::pubkey!("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
 pub const JITO_TIP_AMOUNT: u64 = 10000;
+/// Default cap of derived and escalated Jito tips, in lamports.
+pub const MAX_JITO_TIP_AMOUNT: u64 = 1_000_000;
+/// Mainnet block engine, see [`Network`] for other networks.
 pub const JITO_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf";
 pub const RETRIES: u8 = 5;
+/// Default cap of derived compute unit prices, in micro-lamports.
+pub const MAX_COMPUTE_UNIT_PRICE: u64 = 1_000_000;
+/// Percentile of recent prioritization fees paid by default.
+pub const PRIORITY_FEE_PERCENTILE: u8 = 75;
+/// Margin in percent added to the compute units used in simulation.
+pub const COMPUTE_UNIT_HEADROOM: u64 = 20;
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Accounts `getRecentPrioritizationFees` accepts at most.
+const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+/// Custom errors of specific programs that sending again cannot fix:
+/// insufficient funds of the token and system programs.
+const DETERMINISTIC_PROGRAM_ERRORS: &[(Pubkey, u32)] =
+    &[(spl_token::ID, 1), (system_program::ID, 1)];
+/// Anchor framework errors that sending again cannot fix, whatever Anchor
+/// program raises them: account discriminator not found, account owned by
+/// wrong program and account not initialized.
+const DETERMINISTIC_ANCHOR_ERRORS: &[u32] = &[3001, 3007, 3012];
+/// Delay before the first retry after a failed simulation, doubled on every
+/// further retry.
+const SIMULATION_RETRY_DELAY: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Clone, Copy, Default, EnumString, Display, IntoStaticStr)]
 #[strum(serialize_all = "snake_case")]
+#[allow(non_camel_case_types)]
 pub enum TxSendMethod {
+    /// Jito bundle over the searcher gRPC API, with a whitelisted key.
     #[default]
     JITO,
+    /// Jito bundle over the public block engine JSON-RPC API.
+    JITO_HTTP,
     RPC,
 }
 
+impl TxSendMethod {
+    /// Whether transactions are sent as Jito bundles, paying a tip.
+    pub fn is_jito(&self) -> bool {
+        matches!(self, Self::JITO | Self::JITO_HTTP)
+    }
+}
+
 impl From<&TxSendMethod> for OsStr {
     fn from(value: &TxSendMethod) -> Self {
         let string: &'static str = value.into();
@@ -49,173 +113,382 @@ impl From<&TxSendMethod> for OsStr {
     }
 }
 
-pub async fn escrow_and_store_intent_solana(
-    src_user: &Arc<Keypair>,
-    auctioneer_state: Pubkey,
-    client: &Client<Arc<Keypair>>,
-    intent_id: String,
-    amount_in: u64,
-    mut token_in: Pubkey,
-    dst_user: Pubkey,
-    token_out: String,
-    amount_out: String,
-    timeout_duration: u64,
-    single_domain: bool,
-    tx_send_method: TxSendMethod,
-) -> Result<Signature, String> {
-    let program = match client.program(bridge_escrow::ID) {
-        Ok(prog) => prog,
-        Err(err) => return Err(format!("Failed to get program: {}", err)),
-    };
+/// How the Jito tip of every bundle is chosen.
+///
+/// Derived and escalated tips are at least [`SubmitConfig::jito_tip_amount`]
+/// and at most [`SubmitConfig::max_jito_tip_amount`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TipStrategy {
+    /// `jito_tip_amount` on every attempt.
+    #[default]
+    Fixed,
+    /// Percentile of the tips of recently landed bundles, from the tip floor
+    /// feed: 25, 50, 75, 95 or 99.
+    Percentile(u8),
+    /// `jito_tip_amount`, raised by `step` lamports on every retry.
+    Escalate { step: u64 },
+}
 
-    if token_in == Pubkey::from_str("11111111111111111111111111111111").unwrap() {
-        token_in = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
-        ensure_wsol_balance_blocking(src_user, amount_in, tx_send_method)
-            .await
-            .unwrap();
-    }
+impl FromStr for TipStrategy {
+    type Err = anyhow::Error;
 
-    let user_token_in_addr = get_associated_token_address(&src_user.pubkey(), &token_in);
-    let token_in_escrow_addr = get_associated_token_address(&auctioneer_state, &token_in);
+    /// Parse `fixed`, `percentile:<p>` or `escalate:<step>`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, value) = s.split_once(':').unwrap_or((s, ""));
+        match name {
+            "fixed" if value.is_empty() => Ok(Self::Fixed),
+            "percentile" => {
+                let percentile = value
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid tip percentile {}: {}", value, e))?;
+                if !TIP_FLOOR_PERCENTILES.contains(&percentile) {
+                    bail!("Tip percentile must be one of {:?}", TIP_FLOOR_PERCENTILES);
+                }
+                Ok(Self::Percentile(percentile))
+            }
+            "escalate" => Ok(Self::Escalate {
+                step: value
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid tip step {}: {}", value, e))?,
+            }),
+            _ => bail!(
+                "Invalid tip strategy {}, expected fixed, percentile:<p> or escalate:<step>",
+                s
+            ),
+        }
+    }
+}
 
-    let intent_state =
-        Pubkey::find_program_address(&[b"intent", intent_id.as_bytes()], &bridge_escrow::ID).0;
+impl fmt::Display for TipStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed => write!(f, "fixed"),
+            Self::Percentile(percentile) => write!(f, "percentile:{}", percentile),
+            Self::Escalate { step } => write!(f, "escalate:{}", step),
+        }
+    }
+}
 
-    let current_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// Settings used by [`submit`] to send Solana transactions.
+#[derive(Debug, Clone)]
+pub struct SubmitConfig {
+    /// Block engine regions bundles are raced through.
+    pub jito_block_engines: JitoBlockEngines,
+    /// Tip paid to Jito with every bundle, in lamports, or the lowest tip
+    /// with a strategy other than [`TipStrategy::Fixed`].
+    pub jito_tip_amount: u64,
+    pub jito_tip_strategy: TipStrategy,
+    /// Highest tip a strategy may pay, in lamports.
+    pub max_jito_tip_amount: u64,
+    /// Tip floor feed read by [`TipStrategy::Percentile`].
+    pub jito_tip_floor_url: String,
+    /// Attempts before a transaction is given up, or sent over RPC for Jito.
+    pub retries: u8,
+    pub priority_fee: PriorityFeeConfig,
+    /// Whitelisted keypair authenticating with the Jito searcher API, the
+    /// fee payer's key when not set.
+    pub jito_auth_keypair: Option<Arc<Keypair>>,
+}
 
-    println!("intent_id: {intent_id}");
+impl SubmitConfig {
+    pub fn new(network: &Network) -> Self {
+        Self {
+            jito_block_engines: JitoBlockEngines::new(
+                network
+                    .jito_block_engine_url
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty()),
+            ),
+            jito_tip_amount: JITO_TIP_AMOUNT,
+            jito_tip_strategy: TipStrategy::default(),
+            max_jito_tip_amount: MAX_JITO_TIP_AMOUNT,
+            jito_tip_floor_url: JITO_TIP_FLOOR_URL.to_string(),
+            retries: RETRIES,
+            priority_fee: PriorityFeeConfig::default(),
+            jito_auth_keypair: None,
+        }
+    }
 
-    let new_intent = bridge_escrow::IntentPayload {
-        intent_id: intent_id.clone(),
-        user_in: src_user.pubkey(),
-        user_out: dst_user.to_string(),
-        token_in: token_in,
-        amount_in: amount_in,
-        token_out: token_out,
-        amount_out: amount_out,
-        timeout_timestamp_in_sec: current_timestamp + timeout_duration,
-        single_domain: single_domain,
-    };
+    pub fn jito_block_engines(&self) -> Result<&JitoBlockEngines> {
+        if self.jito_block_engines.is_empty() {
+            bail!(
+                "Jito is not available on this network, use --tx-send-method rpc \
+                 or set JITO_BLOCK_ENGINE_URL"
+            );
+        }
+        Ok(&self.jito_block_engines)
+    }
 
-    loop {
-        let instructions = program
-            .request()
-            .accounts(bridge_escrow::accounts::EscrowAndStoreIntent {
-                user: src_user.pubkey(),
-                user_token_account: Some(user_token_in_addr),
-                auctioneer_state,
-                token_mint: Some(token_in),
-                escrow_token_account: Some(token_in_escrow_addr),
-                intent: intent_state,
-                token_program: anchor_spl::token::ID,
-                associated_token_program: associated_token::ID,
-                system_program: system_program::ID,
+    /// Keypair authenticating `jito` bundles with the searcher API, which only
+    /// accepts whitelisted keys: `jito_auth_keypair`, or else the key of
+    /// `fee_payer` when it is held in process.
+    pub fn jito_auth_keypair(
+        &self,
+        fee_payer: Option<&MantisSolanaSigner>,
+    ) -> Result<Arc<Keypair>> {
+        self.jito_auth_keypair
+            .clone()
+            .or_else(|| fee_payer.and_then(MantisSolanaSigner::keypair))
+            .ok_or_else(|| {
+                anyhow!(
+                    "--tx-send-method jito needs a whitelisted Jito auth keypair, set \
+                     --jito-auth-keypair or use --tx-send-method jito_http"
+                )
             })
-            .args(bridge_escrow::instruction::EscrowAndStoreIntent {
-                new_intent: new_intent.clone(),
-            })
-            .payer(src_user.clone())
-            .instructions()
-            .unwrap();
-        let sig = submit(&program.async_rpc(), src_user.clone(), instructions, tx_send_method).await;
+    }
 
-        match sig {
-            Ok(signature) => break Ok(signature), // Transaction succeeded, exit loop
-            Err(err) if err.to_string().contains("unable to confirm transaction") => {
-                eprintln!("Transaction failed: {}. Retrying...", err);
-                let _ = sleep(Duration::from_secs(1));
+    /// Jito tip of attempt `attempt`, counted from 0, in lamports.
+    ///
+    /// When the tip floor feed cannot be read `jito_tip_amount` is paid.
+    pub async fn jito_tip(&self, attempt: u8) -> u64 {
+        let max_tip = self.max_jito_tip_amount.max(self.jito_tip_amount);
+        match self.jito_tip_strategy {
+            TipStrategy::Fixed => self.jito_tip_amount,
+            TipStrategy::Percentile(percentile) => {
+                match fetch_tip_floor(&self.jito_tip_floor_url).await {
+                    Ok(tip_floor) => tip_floor
+                        .percentile(percentile)
+                        .clamp(self.jito_tip_amount, max_tip),
+                    Err(err) => {
+                        println!(
+                            "Failed to fetch the Jito tip floor, tipping {} lamports: {}",
+                            self.jito_tip_amount, err
+                        );
+                        self.jito_tip_amount
+                    }
+                }
             }
-            Err(err) => {
-                break Err(format!(
-                    "Transaction failed due to a non-retryable error: {}",
-                    err
-                ))
-            } // Break on other errors
+            TipStrategy::Escalate { step } => self
+                .jito_tip_amount
+                .saturating_add(step.saturating_mul(attempt as u64))
+                .min(max_tip),
         }
     }
 }
 
-pub async fn escrow_and_store_intent_cross_chain_solana(
-    src_user: &Arc<Keypair>,
-    auctioneer_state: Pubkey,
-    client: &Client<Arc<Keypair>>,
-    intent_id: String,
-    amount_in: u64,
-    mut token_in: Pubkey,
-    dst_user: String,
-    token_out: String,
-    amount_out: String,
-    timeout_duration: u64,
-    single_domain: bool,
-    tx_send_method: TxSendMethod,
-) -> Result<Signature, String> {
-    let program = match client.program(bridge_escrow::ID) {
-        Ok(prog) => prog,
-        Err(err) => return Err(format!("Failed to get program: {}", err)),
-    };
+/// Outcome of [`submit`].
+#[derive(Debug, Clone, Copy)]
+pub struct SubmitResult {
+    pub signature: Signature,
+    /// Jito tip paid in lamports, `None` when the transaction was sent over
+    /// RPC.
+    pub jito_tip: Option<u64>,
+}
 
-    if token_in == Pubkey::from_str("11111111111111111111111111111111").unwrap() {
-        token_in = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
-        ensure_wsol_balance_blocking(src_user, amount_in, tx_send_method)
-            .await
-            .unwrap();
+/// Transaction failing in simulation.
+#[derive(Debug, Clone)]
+pub struct SimulationFailure {
+    /// Transaction error, e.g. `InstructionError(2, Custom(1))`.
+    pub error: String,
+    /// `error` as a [`TransactionError`], when it is one.
+    pub transaction_error: Option<TransactionError>,
+    /// Program of the failing instruction, when known.
+    pub program_id: Option<Pubkey>,
+    /// Program logs up to the failure.
+    pub logs: Vec<String>,
+}
+
+impl SimulationFailure {
+    /// Simulation of `transaction` that failed with `error`, logging `logs`.
+    pub fn new(transaction: &Transaction, error: TransactionError, logs: Vec<String>) -> Self {
+        let program_id = match &error {
+            TransactionError::InstructionError(index, _) => {
+                let message = &transaction.message;
+                message
+                    .instructions
+                    .get(*index as usize)
+                    .and_then(|ix| message.account_keys.get(ix.program_id_index as usize))
+                    .copied()
+            }
+            _ => None,
+        };
+        Self {
+            error: format!("{:?}", error),
+            transaction_error: Some(error),
+            program_id,
+            logs,
+        }
     }
 
-    let user_token_in_addr = get_associated_token_address(&src_user.pubkey(), &token_in);
-    let token_in_escrow_addr = get_associated_token_address(&auctioneer_state, &token_in);
+    /// `Error Code: .. Error Number: .. Error Message: ..` of the Anchor error
+    /// logged by the failing program, if any.
+    pub fn anchor_error(&self) -> Option<&str> {
+        self.logs
+            .iter()
+            .filter(|log| log.contains("AnchorError"))
+            .find_map(|log| log.find("Error Code:").map(|start| &log[start..]))
+    }
 
-    let intent_state =
-        Pubkey::find_program_address(&[b"intent", intent_id.as_bytes()], &bridge_escrow::ID).0;
+    /// Whether the transaction fails however often it is sent, like for
+    /// insufficient funds or a missing account.
+    pub fn is_deterministic(&self) -> bool {
+        self.transaction_error
+            .as_ref()
+            .is_some_and(|error| is_deterministic_error(error, self.program_id))
+    }
+}
 
-    let current_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+impl fmt::Display for SimulationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Simulation failed: {}", self.error)?;
+        if let Some(anchor_error) = self.anchor_error() {
+            write!(f, "\n{}", anchor_error)?;
+        }
+        for log in &self.logs {
+            write!(f, "\n  {}", log)?;
+        }
+        Ok(())
+    }
+}
 
-    println!("intent_id: {intent_id}");
+impl std::error::Error for SimulationFailure {}
 
-    let new_intent = bridge_escrow::IntentPayload {
-        intent_id: intent_id.clone(),
-        user_in: src_user.pubkey(),
-        user_out: dst_user,
-        token_in: token_in,
-        amount_in: amount_in,
-        token_out: token_out,
-        amount_out: amount_out,
-        timeout_timestamp_in_sec: current_timestamp + timeout_duration,
-        single_domain: single_domain,
+/// Whether `error`, raised by `program_id` for instruction errors, fails
+/// however often the transaction is sent.
+fn is_deterministic_error(error: &TransactionError, program_id: Option<Pubkey>) -> bool {
+    match error {
+        TransactionError::InsufficientFundsForFee
+        | TransactionError::InsufficientFundsForRent { .. }
+        | TransactionError::AccountNotFound
+        | TransactionError::ProgramAccountNotFound
+        | TransactionError::InvalidAccountForFee
+        | TransactionError::InvalidProgramForExecution => true,
+        TransactionError::InstructionError(_, error) => match error {
+            InstructionError::InsufficientFunds
+            | InstructionError::UninitializedAccount
+            | InstructionError::InvalidAccountData
+            | InstructionError::InvalidAccountOwner
+            | InstructionError::IncorrectProgramId => true,
+            InstructionError::Custom(code) => {
+                DETERMINISTIC_ANCHOR_ERRORS.contains(code)
+                    || program_id.is_some_and(|program_id| {
+                        DETERMINISTIC_PROGRAM_ERRORS.contains(&(program_id, *code))
+                    })
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// One of [`JITO_TIP_ACCOUNTS`], picked at random.
+pub fn random_jito_tip_account() -> Pubkey {
+    *JITO_TIP_ACCOUNTS
+        .choose(&mut rand::thread_rng())
+        .unwrap_or(&JITO_ADDRESS)
+}
+
+/// Compute budget added in front of every transaction sent by [`submit`].
+#[derive(Debug, Clone)]
+pub struct PriorityFeeConfig {
+    /// Fixed price in micro-lamports per compute unit. When `None` it is
+    /// derived from the recent prioritization fees of the written accounts,
+    /// within `min_compute_unit_price..=max_compute_unit_price`.
+    pub compute_unit_price: Option<u64>,
+    pub min_compute_unit_price: u64,
+    pub max_compute_unit_price: u64,
+    /// Percentile of the recent prioritization fees to pay.
+    pub percentile: u8,
+    /// Fixed compute unit limit. When `None` the transaction is simulated
+    /// and given [`COMPUTE_UNIT_HEADROOM`] percent more than it used.
+    pub compute_unit_limit: Option<u32>,
+}
+
+impl Default for PriorityFeeConfig {
+    fn default() -> Self {
+        Self {
+            compute_unit_price: None,
+            min_compute_unit_price: 0,
+            max_compute_unit_price: MAX_COMPUTE_UNIT_PRICE,
+            percentile: PRIORITY_FEE_PERCENTILE,
+            compute_unit_limit: None,
+        }
+    }
+}
+
+/// Keys signing Solana transactions.
+///
+/// `owner` holds the tokens and signs as their authority, `fee_payer` pays
+/// transaction fees, Jito tips and the rent of token accounts created for
+/// the owner. Both are the same key unless a fee payer is set.
+#[derive(Debug, Clone)]
+pub struct SolanaSigners {
+    pub owner: Arc<MantisSolanaSigner>,
+    pub fee_payer: Arc<MantisSolanaSigner>,
+}
+
+impl SolanaSigners {
+    pub fn new(owner: Arc<MantisSolanaSigner>) -> Self {
+        Self {
+            fee_payer: owner.clone(),
+            owner,
+        }
+    }
+
+    pub fn with_fee_payer(mut self, fee_payer: Arc<MantisSolanaSigner>) -> Self {
+        self.fee_payer = fee_payer;
+        self
+    }
+
+    /// Distinct signers, the fee payer first.
+    fn signers(&self) -> Vec<&MantisSolanaSigner> {
+        if self.owner.pubkey() == self.fee_payer.pubkey() {
+            vec![&*self.fee_payer]
+        } else {
+            vec![&*self.fee_payer, &*self.owner]
+        }
+    }
+}
+
+/// Escrow `intent` under `intent_id` and store it, for single and
+/// cross-domain intents alike.
+///
+/// The instructions come from [`build_escrow_intent_instructions`] and are
+/// sent with [`submit`].
+pub async fn escrow_and_store_intent_solana(
+    signers: &SolanaSigners,
+    client: &Client<Arc<MantisSolanaSigner>>,
+    network: &Network,
+    submit_config: &SubmitConfig,
+    intent_id: String,
+    intent: &IntentRequest,
+    tx_send_method: TxSendMethod,
+) -> Result<SubmitResult, String> {
+    let program = match client.program(network.bridge_escrow_program) {
+        Ok(prog) => prog,
+        Err(err) => return Err(format!("Failed to get program: {}", err)),
     };
+    let rpc_client = program.async_rpc();
 
-    loop {
-        let instructions = program
-            .request()
-            .accounts(bridge_escrow::accounts::EscrowAndStoreIntent {
-                user: src_user.pubkey(),
-                user_token_account: Some(user_token_in_addr),
-                auctioneer_state,
-                token_mint: Some(token_in),
-                escrow_token_account: Some(token_in_escrow_addr),
-                intent: intent_state,
-                token_program: anchor_spl::token::ID,
-                associated_token_program: associated_token::ID,
-                system_program: system_program::ID,
-            })
-            .args(bridge_escrow::instruction::EscrowAndStoreIntent {
-                new_intent: new_intent.clone(),
-            })
-            .payer(src_user.clone())
-            .instructions()
-            .unwrap();
+    println!("intent_id: {intent_id}");
 
-        let sig = submit(&program.async_rpc(), src_user.clone(), instructions, tx_send_method).await;
+    loop {
+        let instructions = build_escrow_intent_instructions(
+            &rpc_client,
+            program.id(),
+            signers.owner.pubkey(),
+            signers.fee_payer.pubkey(),
+            &intent_id,
+            intent,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        let sig = submit(
+            &rpc_client,
+            submit_config,
+            signers,
+            instructions,
+            tx_send_method,
+        )
+        .await;
 
         match sig {
-            Ok(signature) => break Ok(signature), // Transaction succeeded, exit loop
+            Ok(result) => break Ok(result), // Transaction succeeded, exit loop
             Err(err) if err.to_string().contains("unable to confirm transaction") => {
-                eprintln!("Transaction failed: {}. Retrying...", err);
+                println!("Transaction failed: {}. Retrying...", err);
                 let _ = sleep(Duration::from_secs(1));
             }
             Err(err) => {
@@ -228,17 +501,89 @@ pub async fn escrow_and_store_intent_cross_chain_solana(
     }
 }
 
-pub async fn ensure_wsol_balance_blocking(
-    fee_payer: &Arc<Keypair>,
+/// Instructions escrowing `intent` from `owner` and storing it under
+/// `intent_id`, without sending them.
+///
+/// Native SOL is escrowed as WSOL, so the instructions wrapping it come
+/// first, followed by `EscrowAndStoreIntent`. Single domain intents pay out
+/// to `owner`. `fee_payer` only funds a missing WSOL account; the caller
+/// decides who pays for the transaction.
+pub async fn build_escrow_intent_instructions(
+    rpc_client: &RpcClient,
+    program_id: Pubkey,
+    owner: Pubkey,
+    fee_payer: Pubkey,
+    intent_id: &str,
+    intent: &IntentRequest,
+) -> Result<Vec<Instruction>> {
+    let mut instructions = Vec::new();
+    let amount_in = intent.amount_in.to_u64()?;
+
+    let mut token_in = intent.token_in.as_solana()?;
+    if token_in == system_program::ID {
+        token_in = spl_token::native_mint::ID;
+        instructions
+            .extend(wsol_wrap_instructions(rpc_client, &owner, &fee_payer, amount_in).await?);
+    }
+
+    let dst_user = if intent.is_single_domain() {
+        owner.to_string()
+    } else {
+        intent
+            .dst_user
+            .map(|dst_user| dst_user.to_string())
+            .ok_or_else(|| anyhow!("dst_user is required for cross-domain intents"))?
+    };
+    let current_timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let new_intent = bridge_escrow::IntentPayload {
+        intent_id: intent_id.to_string(),
+        user_in: owner,
+        user_out: dst_user,
+        token_in,
+        amount_in,
+        token_out: intent.token_out.to_string(),
+        amount_out: intent.amount_out.to_string(),
+        timeout_timestamp_in_sec: current_timestamp + intent.timeout,
+        single_domain: intent.is_single_domain(),
+    };
+
+    let auctioneer_state = Pubkey::find_program_address(&[b"auctioneer"], &program_id).0;
+    let intent_state =
+        Pubkey::find_program_address(&[b"intent", intent_id.as_bytes()], &program_id).0;
+    instructions.push(Instruction {
+        program_id,
+        accounts: bridge_escrow::accounts::EscrowAndStoreIntent {
+            user: owner,
+            user_token_account: Some(get_associated_token_address(&owner, &token_in)),
+            auctioneer_state,
+            token_mint: Some(token_in),
+            escrow_token_account: Some(get_associated_token_address(&auctioneer_state, &token_in)),
+            intent: intent_state,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: bridge_escrow::instruction::EscrowAndStoreIntent { new_intent }.data(),
+    });
+
+    Ok(instructions)
+}
+
+/// Instructions wrapping enough SOL for the WSOL account of `owner` to hold
+/// `amount_in`, none when it already does. A missing WSOL account is created
+/// with its rent paid by `fee_payer`.
+pub async fn wsol_wrap_instructions(
+    rpc_client: &RpcClient,
+    owner: &Pubkey,
+    fee_payer: &Pubkey,
     amount_in: u64,
-    tx_send_method: TxSendMethod,
-) -> Result<(), String> {
-    let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
-    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+) -> Result<Vec<Instruction>> {
+    let mut instructions = Vec::new();
 
     // WSOL mint address
     let wsol_mint = Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
-    let wsol_token_address = get_associated_token_address(&fee_payer.pubkey(), &wsol_mint);
+    let wsol_token_address = get_associated_token_address(owner, &wsol_mint);
 
     // Check if WSOL account exists
     let account_data = rpc_client.get_account(&wsol_token_address).await;
@@ -248,21 +593,24 @@ pub async fn ensure_wsol_balance_blocking(
             let token_balance = rpc_client
                 .get_token_account_balance(&wsol_token_address)
                 .await
-                .map_err(|e| format!("Failed to fetch WSOL balance: {}", e))?;
+                .map_err(|e| anyhow!("Failed to fetch WSOL balance: {}", e))?;
             token_balance.amount.parse::<u64>().unwrap_or(0)
         }
         Err(_) => {
             // Account doesn't exist, create it
-            // _create_token_account(&fee_payer.pubkey(), &wsol_mint, fee_payer, &rpc_client)
-            //     .await
-            //     .map_err(|e| format!("Failed to create WSOL token account: {}", e))?;
+            instructions.push(instruction::create_associated_token_account_idempotent(
+                fee_payer,
+                owner,
+                &wsol_mint,
+                &spl_token::ID,
+            ));
             0 // New account, so balance starts at 0
         }
     };
 
     // If the current balance is sufficient, do nothing
     if current_balance >= amount_in {
-        return Ok(());
+        return Ok(instructions);
     }
 
     // Wrap SOL into WSOL
@@ -270,74 +618,299 @@ pub async fn ensure_wsol_balance_blocking(
 
     // Transfer SOL to WSOL account
     let transfer_sol_to_wsol_ix =
-        system_instruction::transfer(&fee_payer.pubkey(), &wsol_token_address, additional_amount);
+        system_instruction::transfer(owner, &wsol_token_address, additional_amount);
 
     // Sync WSOL balance
-    let sync_wsol_balance_ix = sync_native(&spl_token::ID, &wsol_token_address);
+    let sync_wsol_balance_ix = sync_native(&spl_token::ID, &wsol_token_address)?;
 
-    // Build and send the transaction
-    let instructions = vec![transfer_sol_to_wsol_ix, sync_wsol_balance_ix.unwrap()];
+    instructions.extend([transfer_sol_to_wsol_ix, sync_wsol_balance_ix]);
+    Ok(instructions)
+}
+
+/// Read the decimals of an SPL token mint, 9 for native SOL.
+pub async fn fetch_mint_decimals(rpc_client: &RpcClient, mint: &Pubkey) -> Result<u8> {
+    if *mint == system_program::ID || *mint == spl_token::native_mint::ID {
+        return Ok(9);
+    }
+
+    let account = rpc_client
+        .get_account(mint)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch mint {}: {}", mint, e))?;
+    // Token-2022 mints carry extensions after the base mint layout.
+    let data = account
+        .data
+        .get(..Mint::LEN)
+        .ok_or_else(|| anyhow!("Account {} is not a token mint", mint))?;
+    let mint_state = Mint::unpack_from_slice(data)
+        .map_err(|e| anyhow!("Account {} is not a token mint: {}", mint, e))?;
+
+    Ok(mint_state.decimals)
+}
+
+/// Cancel an intent after its timeout and refund the escrowed tokens.
+///
+/// The mint, token accounts and auctioneer are read from the on-chain
+/// intent and auctioneer accounts, so only the intent id is needed.
+pub async fn user_cancel_intent_solana(
+    signers: &SolanaSigners,
+    client: &Client<Arc<MantisSolanaSigner>>,
+    network: &Network,
+    submit_config: &SubmitConfig,
+    intent_id: &str,
+    tx_send_method: TxSendMethod,
+) -> Result<SubmitResult> {
+    let program = client.program(network.bridge_escrow_program)?;
+    let rpc_client = program.async_rpc();
+    let wallet = &signers.owner;
+
+    let auctioneer_state = Pubkey::find_program_address(&[b"auctioneer"], &program.id()).0;
+    let intent_state =
+        Pubkey::find_program_address(&[b"intent", intent_id.as_bytes()], &program.id()).0;
 
-    submit(&rpc_client, fee_payer.clone(), instructions, tx_send_method).await?;
+    let intent: bridge_escrow::Intent = program
+        .account(intent_state)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch intent {}: {}", intent_id, e))?;
+    let auctioneer: bridge_escrow::Auctioneer = program
+        .account(auctioneer_state)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch auctioneer state: {}", e))?;
+
+    if intent.user_in != wallet.pubkey() {
+        return Err(anyhow!(
+            "Intent {} belongs to {}, not {}",
+            intent_id,
+            intent.user_in,
+            wallet.pubkey()
+        ));
+    }
+
+    check_intent_timed_out(&rpc_client, intent_id, intent.timeout_timestamp_in_sec).await?;
+
+    let user_token_account = get_associated_token_address(&wallet.pubkey(), &intent.token_in);
+    let escrow_token_account = get_associated_token_address(&auctioneer_state, &intent.token_in);
+
+    let instructions = program
+        .request()
+        .accounts(bridge_escrow::accounts::OnTimeout {
+            user: wallet.pubkey(),
+            auctioneer_state,
+            auctioneer: auctioneer.authority,
+            intent: Some(intent_state),
+            token_in: Some(intent.token_in),
+            user_token_account: Some(user_token_account),
+            escrow_token_account: Some(escrow_token_account),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(bridge_escrow::instruction::UserCancelIntent {
+            intent_id: intent_id.to_string(),
+        })
+        .payer(signers.fee_payer.clone())
+        .instructions()?;
+
+    submit(
+        &rpc_client,
+        submit_config,
+        signers,
+        instructions,
+        tx_send_method,
+    )
+    .await
+    .map_err(|e| anyhow!(e))
+}
+
+/// Fail unless the cluster clock has passed `timeout_timestamp_in_sec` of the
+/// intent, as the escrow program checks before refunding it.
+async fn check_intent_timed_out(
+    rpc_client: &RpcClient,
+    intent_id: &str,
+    timeout_timestamp_in_sec: u64,
+) -> Result<()> {
+    let now = cluster_unix_timestamp(rpc_client).await?;
+    if now < timeout_timestamp_in_sec {
+        bail!(
+            "Intent {} cannot be cancelled before its timeout, {} seconds left",
+            intent_id,
+            timeout_timestamp_in_sec - now
+        );
+    }
     Ok(())
 }
 
-// fn _user_cancel_intent_solana(
-//     wallet: Arc<Keypair>,
-//     auctioneer_state: Pubkey,
-//     client: Client<Arc<Keypair>>,
-//     intent_id: String,
-// ) {
-//     // Derive necessary accounts
-//     let user = wallet.pubkey();
-//     let user_token_in = get_associated_token_address(
-//         &user,
-//         &Pubkey::from_str("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB").unwrap(),
-//     );
-//     let auctioneer_token_in = get_associated_token_address(
-//         &auctioneer_state,
-//         &Pubkey::from_str("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB").unwrap(),
-//     );
-
-//     // Call the on_timeout method to cancel intent
-//     let program = client.program(bridge_escrow::ID);
-//     let auctioneer = Pubkey::from_str("5zCZ3jk8EZnJyG7fhDqD6tmqiYTLZjik5HUpGMnHrZfC").unwrap();
-
-//     let _sig = program
-//         .unwrap()
-//         .request()
-//         .instruction(ComputeBudgetInstruction::set_compute_unit_limit(1_000_000)) // Optional: Increase compute units if needed
-//         .accounts(bridge_escrow::accounts::OnTimeout {
-//             user: wallet.pubkey(),
-//             auctioneer_state,
-//             auctioneer: auctioneer,
-//             intent: Some(
-//                 Pubkey::find_program_address(
-//                     &[b"intent", intent_id.as_bytes()],
-//                     &bridge_escrow::ID,
-//                 )
-//                 .0,
-//             ),
-//             token_in: Some(
-//                 Pubkey::from_str("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB").unwrap(),
-//             ), // Modify if cross-chain
-//             user_token_account: Some(user_token_in),
-//             escrow_token_account: Some(auctioneer_token_in),
-//             token_program: anchor_spl::token::ID,
-//             associated_token_program: anchor_spl::associated_token::ID,
-//             system_program: solana_program::system_program::ID,
-//         })
-//         .args(bridge_escrow::instruction::UserCancelIntent {
-//             intent_id: intent_id.clone(),
-//         })
-//         .payer(wallet.clone())
-//         .signer(&*wallet)
-//         .send_with_spinner_and_config(RpcSendTransactionConfig {
-//             skip_preflight: true,
-//             ..Default::default()
-//         })
-//         .unwrap();
-// }
+/// `unix_timestamp` of the `Clock` sysvar, the time programs see.
+///
+/// Unlike `getBlockTime`, it is available for the slot just processed.
+async fn cluster_unix_timestamp(rpc_client: &RpcClient) -> Result<u64> {
+    let account = rpc_client
+        .get_account(&sysvar::clock::ID)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch the Clock sysvar: {}", e))?;
+    let clock: Clock =
+        bincode::deserialize(&account.data).map_err(|e| anyhow!("Invalid Clock sysvar: {}", e))?;
+    Ok(clock.unix_timestamp.max(0) as u64)
+}
+
+/// Durable nonce account whose stored blockhash replaces a recent one, so a
+/// transaction signed offline does not expire.
+#[derive(Debug, Clone, Copy)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    /// Authority advancing the nonce, the fee payer when `None`.
+    pub authority: Option<Pubkey>,
+}
+
+/// Build the unsigned transaction escrowing `intent` from `src_user`, to be
+/// signed elsewhere by `src_user` and, when it differs, `fee_payer`.
+///
+/// With `jito_tip` the tip transfer is included, so the signed transaction
+/// can be sent as a bundle. With `nonce` the transaction advances the nonce
+/// and uses its blockhash, otherwise it expires about a minute after it is
+/// built. The compute budget is set from `priority_fee` when building.
+#[allow(clippy::too_many_arguments)]
+pub async fn build_escrow_intent_solana(
+    rpc_client: &RpcClient,
+    program_id: Pubkey,
+    src_user: Pubkey,
+    fee_payer: Pubkey,
+    intent: &IntentRequest,
+    jito_tip: Option<u64>,
+    priority_fee: &PriorityFeeConfig,
+    nonce: Option<DurableNonce>,
+) -> Result<(bridge_escrow::IntentPayload, Transaction)> {
+    let intent_id = generate_random_intent_id();
+
+    let mut instructions = Vec::new();
+    if let Some(tip) = jito_tip {
+        instructions.push(system_instruction::transfer(
+            &fee_payer,
+            &random_jito_tip_account(),
+            tip,
+        ));
+    }
+    instructions.extend(
+        build_escrow_intent_instructions(
+            rpc_client, program_id, src_user, fee_payer, &intent_id, intent,
+        )
+        .await?,
+    );
+
+    // Read the stored payload back from the escrow instruction, past its
+    // 8 byte discriminator.
+    let escrow_ix = instructions
+        .last()
+        .ok_or_else(|| anyhow!("No escrow instruction"))?;
+    let new_intent =
+        bridge_escrow::instruction::EscrowAndStoreIntent::try_from_slice(&escrow_ix.data[8..])?
+            .new_intent;
+
+    // Advancing the nonce has to come first, then the compute budget.
+    let budget =
+        compute_budget_instructions(rpc_client, priority_fee, &fee_payer, &instructions).await?;
+    instructions.splice(0..0, budget);
+    if let Some(nonce) = &nonce {
+        instructions.insert(
+            0,
+            system_instruction::advance_nonce_account(
+                &nonce.account,
+                &nonce.authority.unwrap_or(fee_payer),
+            ),
+        );
+    }
+
+    let blockhash = match &nonce {
+        Some(nonce) => fetch_nonce_blockhash(rpc_client, nonce, fee_payer).await?,
+        None => rpc_client.get_latest_blockhash().await?,
+    };
+    let message = Message::new_with_blockhash(&instructions, Some(&fee_payer), &blockhash);
+
+    Ok((new_intent, Transaction::new_unsigned(message)))
+}
+
+/// Blockhash stored in a durable nonce account, after checking its authority.
+async fn fetch_nonce_blockhash(
+    rpc_client: &RpcClient,
+    nonce: &DurableNonce,
+    fee_payer: Pubkey,
+) -> Result<Hash> {
+    let commitment = rpc_client.commitment();
+    let account = nonblocking::get_account_with_commitment(rpc_client, &nonce.account, commitment)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch nonce account {}: {}", nonce.account, e))?;
+    let data = nonblocking::data_from_account(&account)
+        .map_err(|e| anyhow!("Account {} is not a nonce account: {}", nonce.account, e))?;
+
+    let authority = nonce.authority.unwrap_or(fee_payer);
+    if data.authority != authority {
+        return Err(anyhow!(
+            "Nonce account {} is controlled by {}, not {}",
+            nonce.account,
+            data.authority,
+            authority
+        ));
+    }
+    Ok(data.blockhash())
+}
+
+/// Send a transaction signed elsewhere, as a Jito bundle or over RPC.
+///
+/// The transaction is sent once: it cannot be signed again with a fresh
+/// blockhash. Bundles are simulated first.
+pub async fn send_signed_transaction(
+    rpc_client: &RpcClient,
+    config: &SubmitConfig,
+    transaction: &Transaction,
+    tx_send_method: TxSendMethod,
+) -> Result<Signature> {
+    // The transaction cannot be sent again, so any failure is final.
+    if tx_send_method.is_jito() {
+        if let Some(failure) = simulate_bundle(rpc_client, std::slice::from_ref(transaction))
+            .await
+            .unwrap_or_else(|err| {
+                println!("Failed to simulate bundle, sending it anyway: {}", err);
+                None
+            })
+        {
+            return Err(failure.into());
+        }
+    }
+
+    match tx_send_method {
+        TxSendMethod::JITO => {
+            // Broadcasting needs no Solana key, so the whitelisted auth
+            // keypair must be configured explicitly.
+            let auth_keypair = config.jito_auth_keypair(None)?;
+            let signatures = config
+                .jito_block_engines()?
+                .send_bundle(rpc_client, &auth_keypair, std::slice::from_ref(transaction))
+                .await?;
+            signatures
+                .first()
+                .copied()
+                .ok_or_else(|| anyhow!("No signature found"))
+        }
+        TxSendMethod::JITO_HTTP => config
+            .jito_block_engines()?
+            .send_bundle_http(std::slice::from_ref(transaction))
+            .await?
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow!("No signature found")),
+        TxSendMethod::RPC => rpc_client
+            .send_and_confirm_transaction_with_spinner_and_config(
+                transaction,
+                rpc_client.commitment(),
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("Failed to send transaction: {}", e)),
+    }
+}
 
 pub async fn _create_token_account(
     owner: &Pubkey,
@@ -368,7 +941,7 @@ pub async fn _create_token_account(
         match sig {
             Ok(signature) => break Ok(signature), // Transaction succeeded, exit loop
             Err(err) if err.to_string().contains("unable to confirm transaction") => {
-                eprintln!("Transaction failed: {}. Retrying...", err);
+                println!("Transaction failed: {}. Retrying...", err);
                 let _ = sleep(Duration::from_secs(1));
             }
             Err(err) => {
@@ -381,23 +954,154 @@ pub async fn _create_token_account(
     }
 }
 
+/// `ComputeBudget` instructions setting the compute unit limit and price of
+/// a transaction made of `instructions`, to be placed in front of them.
+///
+/// Simulation failing because the transaction itself fails is an error,
+/// with the program logs. When the fees or the simulation cannot be
+/// fetched the price falls back to its minimum and the limit to the default.
+pub async fn compute_budget_instructions(
+    rpc_client: &RpcClient,
+    config: &PriorityFeeConfig,
+    fee_payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<Vec<Instruction>> {
+    let mut budget = Vec::new();
+
+    let limit = match config.compute_unit_limit {
+        Some(limit) => Some(limit),
+        None => simulate_compute_unit_limit(rpc_client, fee_payer, instructions).await?,
+    };
+    if let Some(limit) = limit {
+        budget.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    }
+
+    let price = match config.compute_unit_price {
+        Some(price) => price,
+        None => recent_compute_unit_price(rpc_client, config, instructions).await,
+    };
+    if price > 0 {
+        budget.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+    }
+
+    Ok(budget)
+}
+
+/// Compute units used by `instructions` in simulation, plus headroom.
+async fn simulate_compute_unit_limit(
+    rpc_client: &RpcClient,
+    fee_payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<Option<u32>> {
+    // Simulate with the budget instructions too, as they use compute units
+    // of their own, and with the highest limit so nothing is cut short.
+    let mut simulated = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+        ComputeBudgetInstruction::set_compute_unit_price(0),
+    ];
+    simulated.extend_from_slice(instructions);
+    let transaction = Transaction::new_unsigned(Message::new(&simulated, Some(fee_payer)));
+
+    let result = match rpc_client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc_client.commitment()),
+                ..Default::default()
+            },
+        )
+        .await
+    {
+        Ok(response) => response.value,
+        Err(err) => {
+            println!("Failed to simulate transaction, using the default compute limit: {err}");
+            return Ok(None);
+        }
+    };
+
+    if let Some(err) = result.err {
+        return Err(
+            SimulationFailure::new(&transaction, err, result.logs.unwrap_or_default()).into(),
+        );
+    }
+    Ok(result.units_consumed.map(|units| {
+        let limit = units * (100 + COMPUTE_UNIT_HEADROOM) / 100;
+        limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }))
+}
+
+/// Percentile of the prioritization fees recently paid to write the
+/// accounts written by `instructions`, within the configured bounds.
+async fn recent_compute_unit_price(
+    rpc_client: &RpcClient,
+    config: &PriorityFeeConfig,
+    instructions: &[Instruction],
+) -> u64 {
+    // Signers are left out, fees paid to write the escrow and intent
+    // accounts are the ones competing with this transaction.
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if meta.is_writable && !meta.is_signer && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts.truncate(MAX_PRIORITIZATION_FEE_ACCOUNTS);
+
+    let mut fees: Vec<u64> = match rpc_client.get_recent_prioritization_fees(&accounts).await {
+        Ok(fees) => fees.into_iter().map(|fee| fee.prioritization_fee).collect(),
+        Err(err) => {
+            println!("Failed to fetch recent prioritization fees, using the minimum: {err}");
+            return config.min_compute_unit_price;
+        }
+    };
+    fees.sort_unstable();
+
+    let price = match fees.len() {
+        0 => 0,
+        len => fees[(len - 1) * config.percentile.min(100) as usize / 100],
+    };
+    price.clamp(
+        config.min_compute_unit_price,
+        config
+            .max_compute_unit_price
+            .max(config.min_compute_unit_price),
+    )
+}
+
 pub async fn submit(
     rpc_client: &RpcClient,
-    fee_payer: Arc<Keypair>,
+    config: &SubmitConfig,
+    signers: &SolanaSigners,
     instructions: Vec<Instruction>,
     tx_send_method: TxSendMethod,
-) -> Result<Signature, String> {
+) -> Result<SubmitResult, String> {
     match tx_send_method {
-        TxSendMethod::JITO => submit_jito(rpc_client, fee_payer, instructions).await,
-        TxSendMethod::RPC => submit_default(rpc_client, fee_payer, instructions).await,
-    }.map_err(|e| e.to_string())
+        TxSendMethod::JITO => submit_jito(rpc_client, config, signers, instructions).await,
+        TxSendMethod::JITO_HTTP => {
+            submit_jito_http(rpc_client, config, signers, instructions).await
+        }
+        TxSendMethod::RPC => submit_default(rpc_client, config, signers, instructions).await,
+    }
+    .map_err(|e| e.to_string())
 }
 
 pub async fn submit_default(
     rpc_client: &RpcClient,
-    fee_payer: Arc<Keypair>,
+    config: &SubmitConfig,
+    signers: &SolanaSigners,
     instructions: Vec<Instruction>,
-) -> Result<Signature> {
+) -> Result<SubmitResult> {
+    let fee_payer = &signers.fee_payer;
+    let mut all_instructions = compute_budget_instructions(
+        rpc_client,
+        &config.priority_fee,
+        &fee_payer.pubkey(),
+        &instructions,
+    )
+    .await?;
+    all_instructions.extend(instructions);
 
     let mut current_try = 0;
     loop {
@@ -407,18 +1111,33 @@ pub async fn submit_default(
             .get_latest_blockhash()
             .await
             .map_err(|e| anyhow!("Failed to fetch blockhash: {}", e))?;
-        let transaction = Transaction::new_signed_with_payer(&instructions, Some(&fee_payer.pubkey()), &[&*fee_payer], recent_blockhash);
+        let mut transaction =
+            Transaction::new_with_payer(&all_instructions, Some(&fee_payer.pubkey()));
+        transaction
+            .try_sign(&signers.signers(), recent_blockhash)
+            .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
 
-        let sig = rpc_client.send_and_confirm_transaction_with_spinner_and_config(&transaction, rpc_client.commitment(), RpcSendTransactionConfig {
-            skip_preflight: true,
-            ..Default::default()
-        }).await;
+        let sig = rpc_client
+            .send_and_confirm_transaction_with_spinner_and_config(
+                &transaction,
+                rpc_client.commitment(),
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..Default::default()
+                },
+            )
+            .await;
 
         match sig {
-            Ok(sig) => return Ok(sig), // Transaction succeeded, exit loop
+            Ok(signature) => {
+                return Ok(SubmitResult {
+                    signature,
+                    jito_tip: None,
+                })
+            } // Transaction succeeded, exit loop
             Err(err) if err.to_string().contains("unable to confirm transaction") => {
-                eprintln!("Transaction failed: {}. Retrying...", err);
-                if current_try == RETRIES {
+                println!("Transaction failed: {}. Retrying...", err);
+                if current_try >= config.retries {
                     return Err(anyhow!("Failed to send transaction: {}", err));
                 }
                 std::thread::sleep(Duration::from_secs(1));
@@ -435,59 +1154,504 @@ pub async fn submit_default(
 
 pub async fn submit_jito(
     rpc_client: &RpcClient,
-    fee_payer: Arc<Keypair>,
+    config: &SubmitConfig,
+    signers: &SolanaSigners,
     instructions: Vec<Instruction>,
-) -> Result<Signature> {
-    let ix = system_instruction::transfer(
-        &fee_payer.pubkey(),
-        &JITO_ADDRESS,
-        JITO_TIP_AMOUNT,
-    );
+) -> Result<SubmitResult> {
+    let fee_payer = &signers.fee_payer;
+    let block_engines = config.jito_block_engines()?;
+    let auth_keypair = config.jito_auth_keypair(Some(fee_payer.as_ref()))?;
+    let budget = jito_compute_budget(rpc_client, config, signers, &instructions).await?;
 
-    let mut all_instructions = vec![ix];
-    all_instructions.extend_from_slice(instructions.as_slice());
+    let mut current_try = 0;
+    let mut simulation_failure = None;
+    while current_try < config.retries {
+        let tip = config.jito_tip(current_try).await;
+        let mut cloned_tx = Transaction::new_with_payer(
+            &with_jito_tip(&budget, &fee_payer.pubkey(), tip, &instructions),
+            Some(&fee_payer.pubkey()),
+        );
+        let blockhash = rpc_client.get_latest_blockhash().await?;
+        cloned_tx
+            .try_sign(&signers.signers(), blockhash)
+            .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
 
-    let tx = Transaction::new_with_payer(all_instructions.as_slice(), Some(&fee_payer.pubkey()));
+        simulation_failure = simulate_before_sending(rpc_client, &cloned_tx).await?;
+        if simulation_failure.is_some() {
+            sleep(simulation_backoff(current_try)).await;
+            current_try += 1;
+            continue;
+        }
 
-    let mut current_try = 0;
-    let mut signature: Signature = Signature::default();
-    while current_try < RETRIES {
-        let mut cloned_tx = tx.clone();
-        let mut client =
-            jito_searcher_client::get_searcher_client(&JITO_BLOCK_ENGINE_URL, &fee_payer)
-                .await?;
-        let mut bundle_results_subscription = client
-            .subscribe_bundle_results(SubscribeBundleResultsRequest {})
-            .await?
-            .into_inner();
+        match block_engines
+            .send_bundle(rpc_client, &auth_keypair, &[cloned_tx])
+            .await
+        {
+            Ok(sigs) => {
+                return Ok(SubmitResult {
+                    signature: *sigs.first().ok_or_else(|| anyhow!("No signature found"))?,
+                    jito_tip: Some(tip),
+                });
+            }
+            Err(err) => {
+                println!("Bundle failed: {}. Retrying...", err);
+                current_try += 1;
+            }
+        }
+    }
+    if let Some(failure) = simulation_failure {
+        return Err(still_failing(failure, config.retries));
+    }
+    println!("Failed to send transaction with the tries, Sending it through RPC Now");
+    submit_default(rpc_client, config, signers, instructions).await
+}
+
+/// Send `instructions` as a Jito bundle over the block engine JSON-RPC API,
+/// falling back to RPC like [`submit_jito`].
+pub async fn submit_jito_http(
+    rpc_client: &RpcClient,
+    config: &SubmitConfig,
+    signers: &SolanaSigners,
+    instructions: Vec<Instruction>,
+) -> Result<SubmitResult> {
+    let block_engines = config.jito_block_engines()?;
+    let budget = jito_compute_budget(rpc_client, config, signers, &instructions).await?;
+    let fee_payer = signers.fee_payer.pubkey();
 
+    let mut simulation_failure = None;
+    for attempt in 0..config.retries {
+        let tip = config.jito_tip(attempt).await;
         let blockhash = rpc_client.get_latest_blockhash().await?;
-        cloned_tx.sign(&[&*fee_payer], blockhash);
+        let mut transaction = Transaction::new_with_payer(
+            &with_jito_tip(&budget, &fee_payer, tip, &instructions),
+            Some(&fee_payer),
+        );
+        transaction
+            .try_sign(&signers.signers(), blockhash)
+            .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
 
-        let signatures = jito_searcher_client::send_bundle_with_confirmation(
-            &[cloned_tx.into()],
-            &rpc_client,
-            &mut client,
-            &mut bundle_results_subscription,
-        )
-        .await
-        .or_else(|e| {
-            println!("This is error {:?}", e);
-            Err(e)
-        });
-
-        if let Ok(sigs) = signatures {
-            signature = *sigs.first().ok_or_else(|| anyhow!("No signature found"))?;
-            return Ok(signature);
-        } else {
-            current_try += 1;
+        simulation_failure = simulate_before_sending(rpc_client, &transaction).await?;
+        if simulation_failure.is_some() {
+            sleep(simulation_backoff(attempt)).await;
             continue;
         }
+
+        match block_engines.send_bundle_http(&[transaction]).await {
+            Ok(signatures) => {
+                return Ok(SubmitResult {
+                    signature: *signatures
+                        .first()
+                        .ok_or_else(|| anyhow!("No signature found"))?,
+                    jito_tip: Some(tip),
+                })
+            }
+            Err(err) => println!("Bundle failed: {}. Retrying...", err),
+        }
     }
-    if current_try == RETRIES {
-        println!("Failed to send transaction with the tries, Sending it through RPC Now");
-        submit_default(rpc_client, fee_payer, instructions).await
-    } else {
-        Ok(signature)
+
+    if let Some(failure) = simulation_failure {
+        return Err(still_failing(failure, config.retries));
+    }
+    println!("Failed to send transaction with the tries, Sending it through RPC Now");
+    submit_default(rpc_client, config, signers, instructions).await
+}
+
+/// Simulate the bundle of `transaction` before it is sent.
+///
+/// Returns the failure to retry after when it may not happen again, an
+/// error for a failure that will. When the bundle cannot be simulated it is
+/// sent anyway.
+async fn simulate_before_sending(
+    rpc_client: &RpcClient,
+    transaction: &Transaction,
+) -> Result<Option<SimulationFailure>> {
+    match simulate_bundle(rpc_client, std::slice::from_ref(transaction)).await {
+        Ok(None) => Ok(None),
+        Ok(Some(failure)) if failure.is_deterministic() => Err(failure.into()),
+        Ok(Some(failure)) => {
+            println!("{}\nRetrying...", failure);
+            Ok(Some(failure))
+        }
+        Err(err) => {
+            println!("Failed to simulate bundle, sending it anyway: {}", err);
+            Ok(None)
+        }
+    }
+}
+
+/// Delay before retrying after a failed simulation on attempt `attempt`.
+fn simulation_backoff(attempt: u8) -> Duration {
+    SIMULATION_RETRY_DELAY * 2u32.pow(attempt.min(4) as u32)
+}
+
+/// Error of a bundle still failing in simulation on its last attempt, which
+/// is not sent over RPC to fail the same way.
+fn still_failing(failure: SimulationFailure, retries: u8) -> anyhow::Error {
+    anyhow::Error::new(failure).context(format!(
+        "Bundle still failing in simulation after {} attempts, not sending it over RPC",
+        retries
+    ))
+}
+
+/// Compute budget of a bundle transaction, sized with a tip transfer in
+/// front of `instructions`.
+async fn jito_compute_budget(
+    rpc_client: &RpcClient,
+    config: &SubmitConfig,
+    signers: &SolanaSigners,
+    instructions: &[Instruction],
+) -> Result<Vec<Instruction>> {
+    let fee_payer = signers.fee_payer.pubkey();
+    let tipped_instructions = with_jito_tip(&[], &fee_payer, config.jito_tip_amount, instructions);
+    compute_budget_instructions(
+        rpc_client,
+        &config.priority_fee,
+        &fee_payer,
+        &tipped_instructions,
+    )
+    .await
+}
+
+/// `budget`, then `tip` lamports from `fee_payer` to a random Jito tip
+/// account, then `instructions`.
+fn with_jito_tip(
+    budget: &[Instruction],
+    fee_payer: &Pubkey,
+    tip: u64,
+    instructions: &[Instruction],
+) -> Vec<Instruction> {
+    let mut all_instructions = budget.to_vec();
+    all_instructions.push(system_instruction::transfer(
+        fee_payer,
+        &random_jito_tip_account(),
+        tip,
+    ));
+    all_instructions.extend_from_slice(instructions);
+    all_instructions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::NetworkName;
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::instruction::AccountMeta;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    /// Escrow transaction with a compute budget, a system transfer, a token
+    /// transfer and an escrow instruction, in this order.
+    fn simulated_transaction() -> Transaction {
+        let payer = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            system_instruction::transfer(&payer, &Pubkey::new_unique(), 1),
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &token_account,
+                &Pubkey::new_unique(),
+                &payer,
+                &[],
+                1,
+            )
+            .unwrap(),
+            Instruction::new_with_bytes(bridge_escrow::ID, &[], vec![]),
+        ];
+        Transaction::new_unsigned(Message::new(&instructions, Some(&payer)))
+    }
+
+    fn failure(error: TransactionError) -> SimulationFailure {
+        SimulationFailure::new(&simulated_transaction(), error, Vec::new())
+    }
+
+    #[test]
+    fn deterministic_simulation_failures() {
+        let deterministic = [
+            TransactionError::InsufficientFundsForFee,
+            TransactionError::AccountNotFound,
+            TransactionError::InsufficientFundsForRent { account_index: 1 },
+            TransactionError::InstructionError(2, InstructionError::InsufficientFunds),
+            TransactionError::InstructionError(1, InstructionError::Custom(1)),
+            TransactionError::InstructionError(2, InstructionError::Custom(1)),
+            TransactionError::InstructionError(3, InstructionError::Custom(3012)),
+        ];
+        for error in deterministic {
+            assert!(failure(error.clone()).is_deterministic(), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn retryable_simulation_failures() {
+        let retryable = [
+            TransactionError::BlockhashNotFound,
+            TransactionError::AlreadyProcessed,
+            TransactionError::InstructionError(2, InstructionError::Custom(6001)),
+            TransactionError::InstructionError(0, InstructionError::ComputationalBudgetExceeded),
+            // Custom(1) of programs other than the token and system programs.
+            TransactionError::InstructionError(3, InstructionError::Custom(1)),
+            TransactionError::InstructionError(9, InstructionError::Custom(1)),
+        ];
+        for error in retryable {
+            assert!(!failure(error.clone()).is_deterministic(), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn simulation_failures_are_classified_by_error_not_logs() {
+        let logged = SimulationFailure {
+            error: "\"BlockhashNotFound\"".to_string(),
+            transaction_error: None,
+            program_id: None,
+            logs: vec!["Program log: Error: insufficient funds".to_string()],
+        };
+        assert!(!logged.is_deterministic());
+
+        let error = serde_json::from_value(serde_json::json!({
+            "InstructionError": [2, { "Custom": 1 }]
+        }))
+        .unwrap();
+        assert!(failure(error).is_deterministic());
+    }
+
+    #[test]
+    fn simulation_backoff_doubles_up_to_a_cap() {
+        assert_eq!(simulation_backoff(0), SIMULATION_RETRY_DELAY);
+        assert_eq!(simulation_backoff(1), SIMULATION_RETRY_DELAY * 2);
+        assert_eq!(simulation_backoff(3), SIMULATION_RETRY_DELAY * 8);
+        assert_eq!(simulation_backoff(4), simulation_backoff(u8::MAX));
+    }
+
+    #[test]
+    fn tip_strategies_are_parsed() {
+        for (strategy, parsed) in [
+            ("fixed", TipStrategy::Fixed),
+            ("percentile:75", TipStrategy::Percentile(75)),
+            ("escalate:1000", TipStrategy::Escalate { step: 1000 }),
+        ] {
+            assert_eq!(strategy.parse::<TipStrategy>().unwrap(), parsed);
+            assert_eq!(parsed.to_string(), strategy);
+        }
+    }
+
+    #[test]
+    fn invalid_tip_strategies_are_rejected() {
+        for strategy in [
+            "",
+            "fixed:1",
+            "percentile",
+            "percentile:60",
+            "percentile:high",
+            "escalate",
+            "escalate:-1",
+            "random",
+        ] {
+            assert!(strategy.parse::<TipStrategy>().is_err(), "{:?}", strategy);
+        }
+    }
+
+    fn tip_config(strategy: TipStrategy) -> SubmitConfig {
+        SubmitConfig {
+            jito_tip_amount: 5_000,
+            jito_tip_strategy: strategy,
+            max_jito_tip_amount: 500_000,
+            ..SubmitConfig::new(&Network::new(NetworkName::Localnet))
+        }
+    }
+
+    #[tokio::test]
+    async fn escalating_tips_are_capped() {
+        let config = tip_config(TipStrategy::Escalate { step: 200_000 });
+        let mut tips = Vec::new();
+        for attempt in 0..5 {
+            tips.push(config.jito_tip(attempt).await);
+        }
+        assert_eq!(tips, [5_000, 205_000, 405_000, 500_000, 500_000]);
+
+        let config = tip_config(TipStrategy::Escalate { step: u64::MAX });
+        assert_eq!(config.jito_tip(u8::MAX).await, 500_000);
+    }
+
+    #[tokio::test]
+    async fn tips_never_go_below_the_tip_amount() {
+        let mut config = tip_config(TipStrategy::Escalate { step: 1_000 });
+        config.max_jito_tip_amount = 1_000;
+        assert_eq!(config.jito_tip(3).await, 5_000);
+        assert_eq!(tip_config(TipStrategy::Fixed).jito_tip(3).await, 5_000);
+    }
+
+    /// Answer every request on a free local port with the JSON `body`.
+    fn serve_json(body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || crate::http::serve(listener, |_, _| Ok(body.clone())));
+        url
+    }
+
+    #[tokio::test]
+    async fn percentile_tips_are_clamped() {
+        let tip_floor = serde_json::json!([{
+            "landed_tips_25th_percentile": 0.000001,
+            "landed_tips_50th_percentile": 0.00001,
+            "landed_tips_75th_percentile": 0.0001,
+            "landed_tips_95th_percentile": 0.001,
+            "landed_tips_99th_percentile": 0.01,
+        }]);
+        let url = serve_json(tip_floor.to_string());
+
+        let mut tips = Vec::new();
+        for percentile in TIP_FLOOR_PERCENTILES {
+            let mut config = tip_config(TipStrategy::Percentile(percentile));
+            config.jito_tip_floor_url = url.clone();
+            tips.push(config.jito_tip(0).await);
+        }
+        assert_eq!(tips, [5_000, 10_000, 100_000, 500_000, 500_000]);
+    }
+
+    #[tokio::test]
+    async fn percentile_tips_fall_back_to_the_tip_amount() {
+        let mut config = tip_config(TipStrategy::Percentile(75));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        config.jito_tip_floor_url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        assert_eq!(config.jito_tip(0).await, 5_000);
+    }
+
+    /// Mock node answering `getRecentPrioritizationFees` once with `fees`.
+    fn prioritization_fees(fees: &[u64]) -> RpcClient {
+        let fees: Vec<_> = fees
+            .iter()
+            .map(|fee| serde_json::json!({ "slot": 1, "prioritizationFee": fee }))
+            .collect();
+        RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            HashMap::from([(
+                RpcRequest::GetRecentPrioritizationFees,
+                serde_json::json!(fees),
+            )]),
+        )
+    }
+
+    fn escrow_instruction() -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new(Pubkey::new_unique(), false),
+            ],
+        )
+    }
+
+    async fn price(fees: &[u64], config: &PriorityFeeConfig) -> u64 {
+        let rpc_client = prioritization_fees(fees);
+        recent_compute_unit_price(&rpc_client, config, &[escrow_instruction()]).await
+    }
+
+    #[tokio::test]
+    async fn compute_unit_price_is_a_percentile_of_recent_fees() {
+        let fees = [30, 0, 100, 50, 10, 90, 20, 70, 40, 80, 60];
+        for (percentile, expected) in [(0, 0), (50, 50), (75, 70), (100, 100), (200, 100)] {
+            let config = PriorityFeeConfig {
+                percentile,
+                ..Default::default()
+            };
+            assert_eq!(price(&fees, &config).await, expected, "{}", percentile);
+        }
+    }
+
+    #[tokio::test]
+    async fn compute_unit_price_is_clamped() {
+        let fees = [1_000, 2_000_000];
+        let config = PriorityFeeConfig {
+            percentile: 100,
+            ..Default::default()
+        };
+        assert_eq!(price(&fees, &config).await, MAX_COMPUTE_UNIT_PRICE);
+
+        let config = PriorityFeeConfig {
+            percentile: 0,
+            min_compute_unit_price: 5_000,
+            ..Default::default()
+        };
+        assert_eq!(price(&fees, &config).await, 5_000);
+
+        // A minimum above the maximum wins.
+        let config = PriorityFeeConfig {
+            min_compute_unit_price: 5_000,
+            max_compute_unit_price: 100,
+            ..Default::default()
+        };
+        assert_eq!(price(&fees, &config).await, 5_000);
+    }
+
+    #[tokio::test]
+    async fn compute_unit_price_falls_back_to_the_minimum() {
+        let config = PriorityFeeConfig {
+            min_compute_unit_price: 1_000,
+            ..Default::default()
+        };
+        assert_eq!(price(&[], &config).await, 1_000);
+
+        let unreachable = RpcClient::new_mock("fails".to_string());
+        let instructions = [escrow_instruction()];
+        assert_eq!(
+            recent_compute_unit_price(&unreachable, &config, &instructions).await,
+            1_000
+        );
+    }
+
+    /// Mock node answering `getAccountInfo` once with the `Clock` sysvar at
+    /// `unix_timestamp`.
+    fn clock_at(unix_timestamp: i64) -> RpcClient {
+        let clock = Clock {
+            unix_timestamp,
+            ..Clock::default()
+        };
+        let data = BASE64.encode(bincode::serialize(&clock).unwrap());
+        RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            HashMap::from([(
+                RpcRequest::GetAccountInfo,
+                serde_json::json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "lamports": 1_169_280,
+                        "data": [data, "base64"],
+                        "owner": sysvar::ID.to_string(),
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": 40,
+                    },
+                }),
+            )]),
+        )
+    }
+
+    #[tokio::test]
+    async fn intents_cannot_be_cancelled_before_their_timeout() {
+        let err = check_intent_timed_out(&clock_at(1_700_000_000), "intent", 1_700_000_060)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("60 seconds left"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn timed_out_intents_can_be_cancelled() {
+        for timeout in [1_699_999_999, 1_700_000_000] {
+            check_intent_timed_out(&clock_at(1_700_000_000), "intent", timeout)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelling_fails_without_the_cluster_clock() {
+        let unreachable = RpcClient::new_mock("fails".to_string());
+        assert!(check_intent_timed_out(&unreachable, "intent", 0)
+            .await
+            .is_err());
     }
 }
@@ -0,0 +1,354 @@
+//! `jito_http` against the stand-in block engine, run in process in front of
+//! a fake Solana RPC node.
+
+#[path = "../examples/common/block_engine.rs"]
+mod block_engine;
+#[path = "../examples/common/http.rs"]
+mod http;
+
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use mantis_sdk::jito::{simulate_bundle, JitoHttpClient};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+use crate::block_engine::BlockEngine;
+
+const SLOT: u64 = 42;
+
+/// JSON-RPC methods a server has been called with, in order.
+type Calls = Arc<Mutex<Vec<String>>>;
+
+/// Solana RPC node answering what the block engine and [`simulate_bundle`]
+/// call, landing every transaction right away.
+#[derive(Clone)]
+struct FakeValidator {
+    /// Whether `simulateBundle` is known, like on Jito's RPC nodes.
+    simulate_bundle: bool,
+    /// `err` of every simulated transaction.
+    simulation_err: Value,
+}
+
+impl FakeValidator {
+    fn handle(&self, request: Value) -> Result<String> {
+        let params = &request["params"];
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "getVersion" => json!({ "solana-core": "1.17.30", "feature-set": 0 }),
+            "getSlot" => json!(SLOT),
+            "sendTransaction" => {
+                let bytes = BASE64.decode(params[0].as_str().unwrap_or_default())?;
+                let transaction: Transaction = bincode::deserialize(&bytes)?;
+                json!(transaction.signatures[0].to_string())
+            }
+            "getSignatureStatuses" => {
+                let status = json!({
+                    "slot": SLOT,
+                    "confirmations": null,
+                    "status": { "Ok": null },
+                    "err": null,
+                    "confirmationStatus": "finalized",
+                });
+                let signatures = params[0].as_array().map_or(0, Vec::len);
+                with_context(json!(vec![status; signatures]))
+            }
+            "simulateTransaction" => with_context(json!({
+                "err": self.simulation_err,
+                "logs": ["Program log: simulated"],
+                "accounts": null,
+                "unitsConsumed": 150,
+                "returnData": null,
+            })),
+            "simulateBundle" if self.simulate_bundle => with_context(json!({
+                "summary": if self.simulation_err.is_null() {
+                    json!("succeeded")
+                } else {
+                    json!({ "failed": { "error": self.simulation_err, "tx_signature": null } })
+                },
+                "transactionResults": [{
+                    "err": self.simulation_err,
+                    "logs": ["Program log: simulated"],
+                }],
+            })),
+            method => {
+                let error = json!({ "code": -32601, "message": format!("Unknown {}", method) });
+                return Ok(
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }).to_string(),
+                );
+            }
+        };
+        Ok(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string())
+    }
+}
+
+fn with_context(value: Value) -> Value {
+    json!({ "context": { "slot": SLOT }, "value": value })
+}
+
+/// Serve the handler `new_handler` builds on a free local port, returning
+/// its URL and the methods it is called with.
+fn spawn_server<H>(new_handler: impl FnOnce() -> H + Send + 'static) -> (String, Calls)
+where
+    H: FnMut(&str, Value) -> Result<String>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let calls = Calls::default();
+    let recorded = calls.clone();
+    thread::spawn(move || {
+        let mut handle = new_handler();
+        http::serve(listener, |path, request| {
+            let method = request["method"].as_str().unwrap_or_default();
+            recorded.lock().unwrap().push(method.to_string());
+            handle(path, request)
+        })
+    });
+    (url, calls)
+}
+
+fn spawn_validator(validator: FakeValidator) -> (String, Calls) {
+    spawn_server(move || move |_: &str, request| validator.handle(request))
+}
+
+/// Stand-in block engine in front of a validator landing every transaction.
+fn spawn_block_engine() -> (String, Calls) {
+    let (validator_url, _) = spawn_validator(FakeValidator {
+        simulate_bundle: false,
+        simulation_err: Value::Null,
+    });
+    spawn_server(move || {
+        // The blocking RPC client owns a runtime, so it is built and
+        // dropped outside of the test's runtime.
+        let mut engine = BlockEngine::new(validator_url);
+        move |path: &str, request| engine.handle(path, request)
+    })
+}
+
+/// Block engine reporting every bundle `Invalid` to its first
+/// `invalid_polls` status polls, then landed and finalized.
+fn spawn_slow_block_engine(invalid_polls: usize) -> (String, Calls) {
+    spawn_server(move || {
+        let mut polls = 0;
+        move |_: &str, request: Value| {
+            let bundle_id = &request["params"][0][0];
+            let result = match request["method"].as_str().unwrap_or_default() {
+                "sendBundle" => json!("bundle"),
+                "getInflightBundleStatuses" => {
+                    polls += 1;
+                    let status = if polls > invalid_polls {
+                        "Landed"
+                    } else {
+                        "Invalid"
+                    };
+                    with_context(json!([{
+                        "bundle_id": bundle_id,
+                        "status": status,
+                        "landed_slot": null,
+                    }]))
+                }
+                "getBundleStatuses" => with_context(json!([{
+                    "bundle_id": bundle_id,
+                    "transactions": [Keypair::new().sign_message(b"bundle").to_string()],
+                    "slot": SLOT,
+                    "confirmation_status": "finalized",
+                    "err": { "Ok": null },
+                }])),
+                method => anyhow::bail!("Unknown {}", method),
+            };
+            Ok(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string())
+        }
+    })
+}
+
+fn transfer() -> Transaction {
+    let payer = Keypair::new();
+    let instruction = system_instruction::transfer(&payer.pubkey(), &Keypair::new().pubkey(), 1);
+    Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[&payer],
+        Hash::default(),
+    )
+}
+
+#[tokio::test]
+async fn bundle_statuses_follow_sent_bundle() {
+    let (url, calls) = spawn_block_engine();
+    let client = JitoHttpClient::new(&url);
+    let transaction = transfer();
+
+    let bundle_id = client
+        .send_bundle(std::slice::from_ref(&transaction))
+        .await
+        .unwrap();
+    assert_eq!(bundle_id, transaction.signatures[0].to_string());
+    let bundle_ids = [bundle_id.clone(), "unknown".to_string()];
+
+    let inflight = client
+        .get_inflight_bundle_statuses(&bundle_ids)
+        .await
+        .unwrap();
+    let landed = inflight[0].as_ref().unwrap();
+    assert_eq!(landed.bundle_id, bundle_id);
+    assert_eq!(landed.status, "Landed");
+    assert_eq!(landed.landed_slot, Some(SLOT));
+    assert!(inflight[1].is_none());
+
+    let statuses = client.get_bundle_statuses(&bundle_ids).await.unwrap();
+    let status = statuses[0].as_ref().unwrap();
+    assert_eq!(status.transactions, vec![bundle_id.clone()]);
+    assert_eq!(status.slot, SLOT);
+    assert_eq!(status.confirmation_status.as_deref(), Some("finalized"));
+    assert_eq!(status.err, json!({ "Ok": null }));
+    assert!(statuses[1].is_none());
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            "sendBundle",
+            "getInflightBundleStatuses",
+            "getBundleStatuses"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn send_bundle_with_confirmation_returns_signatures() {
+    let (url, calls) = spawn_block_engine();
+    let transaction = transfer();
+
+    let signatures = JitoHttpClient::new(&url)
+        .send_bundle_with_confirmation(std::slice::from_ref(&transaction))
+        .await
+        .unwrap();
+    assert_eq!(signatures, transaction.signatures);
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            "sendBundle",
+            "getInflightBundleStatuses",
+            "getBundleStatuses"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unknown_path_is_rejected() {
+    let (url, _) = spawn_block_engine();
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/transactions", url))
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": "sendBundle", "params": [] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn simulate_bundle_falls_back_to_simulate_transaction() {
+    let (url, calls) = spawn_validator(FakeValidator {
+        simulate_bundle: false,
+        simulation_err: Value::Null,
+    });
+    let rpc_client = RpcClient::new(url);
+
+    let failure = simulate_bundle(&rpc_client, &[transfer()]).await.unwrap();
+    assert!(failure.is_none());
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.first().map(String::as_str), Some("simulateBundle"));
+    assert_eq!(
+        calls.last().map(String::as_str),
+        Some("simulateTransaction")
+    );
+}
+
+#[tokio::test]
+async fn simulate_transaction_failure_is_classified() {
+    let (url, _) = spawn_validator(FakeValidator {
+        simulate_bundle: false,
+        simulation_err: json!({ "InstructionError": [0, { "Custom": 1 }] }),
+    });
+    let rpc_client = RpcClient::new(url);
+
+    let failure = simulate_bundle(&rpc_client, &[transfer()])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        failure.transaction_error,
+        Some(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(1)
+        ))
+    );
+    // Custom(1) of the system transfer is its insufficient funds error.
+    assert_eq!(failure.program_id, Some(system_program::ID));
+    assert!(failure.is_deterministic());
+    assert_eq!(failure.logs, ["Program log: simulated"]);
+}
+
+#[tokio::test]
+async fn simulate_bundle_failure_is_classified() {
+    let (url, calls) = spawn_validator(FakeValidator {
+        simulate_bundle: true,
+        simulation_err: json!("BlockhashNotFound"),
+    });
+    let rpc_client = RpcClient::new(url);
+
+    let failure = simulate_bundle(&rpc_client, &[transfer()])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        failure.transaction_error,
+        Some(TransactionError::BlockhashNotFound)
+    );
+    assert!(!failure.is_deterministic());
+    assert_eq!(*calls.lock().unwrap(), ["simulateBundle"]);
+}
+
+#[tokio::test]
+async fn bundles_may_be_invalid_right_after_sending() {
+    let (url, calls) = spawn_slow_block_engine(2);
+
+    let signatures = JitoHttpClient::new(&url)
+        .send_bundle_with_confirmation(&[transfer()])
+        .await
+        .unwrap();
+    assert_eq!(signatures.len(), 1);
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            "sendBundle",
+            "getInflightBundleStatuses",
+            "getInflightBundleStatuses",
+            "getInflightBundleStatuses",
+            "getBundleStatuses"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn bundles_that_stay_invalid_are_dropped() {
+    let (url, calls) = spawn_slow_block_engine(usize::MAX);
+
+    let err = JitoHttpClient::new(&url)
+        .send_bundle_with_confirmation(&[transfer()])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("still invalid"), "{}", err);
+    assert!(!calls
+        .lock()
+        .unwrap()
+        .contains(&"getBundleStatuses".to_string()));
+}
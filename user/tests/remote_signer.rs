@@ -0,0 +1,154 @@
+//! Remote signers of `mantis_sdk::signer` against the stand-in signer, run in
+//! process with keys whose signatures are known.
+
+#[path = "../examples/common/http.rs"]
+mod http;
+#[path = "../examples/common/signer.rs"]
+mod signer;
+
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use ethers::signers::{LocalWallet, Signer as _};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, TransactionRequest, U256};
+use ethers::utils::hex;
+use mantis_sdk::keys::ethereum_wallet_from_hex;
+use mantis_sdk::signer::{
+    MantisEthereumSigner, MantisSolanaSigner, RemoteEthereumSigner, RemoteSolanaSigner,
+};
+use serde_json::{json, Value};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+use crate::signer::Keys;
+
+/// First Hardhat and Anvil development key.
+const ETHEREUM_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+const CHAIN_ID: u64 = 5;
+
+/// Paths and bodies of the requests a server has been sent, in order.
+type Requests = Arc<Mutex<Vec<(String, Value)>>>;
+
+/// Serve the stand-in signer holding `keys` on a free local port, returning
+/// its URL and the requests it is sent.
+fn spawn_signer(keys: Keys) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    let recorded = requests.clone();
+    thread::spawn(move || {
+        http::serve(listener, |path, body| {
+            recorded
+                .lock()
+                .unwrap()
+                .push((path.to_string(), body.clone()));
+            keys.sign(path, body)
+        })
+    });
+    (url, requests)
+}
+
+fn ethereum_signer() -> (LocalWallet, MantisEthereumSigner, Requests) {
+    let wallet = ethereum_wallet_from_hex(ETHEREUM_KEY).unwrap();
+    let (url, requests) = spawn_signer(Keys {
+        solana: None,
+        ethereum: Some(wallet.clone()),
+    });
+    let remote = RemoteEthereumSigner::new(url, wallet.address());
+    (wallet, remote.into(), requests)
+}
+
+fn solana_signer() -> (Keypair, MantisSolanaSigner) {
+    let keypair = Keypair::new();
+    let (url, _) = spawn_signer(Keys {
+        solana: Some(Keypair::from_bytes(&keypair.to_bytes()).unwrap()),
+        ethereum: None,
+    });
+    let remote = RemoteSolanaSigner::new(format!("{}/solana", url), keypair.pubkey());
+    (keypair, remote.into())
+}
+
+#[tokio::test]
+async fn remote_ethereum_transactions_carry_eip155_v() {
+    let (wallet, remote, requests) = ethereum_signer();
+    let wallet = wallet.with_chain_id(CHAIN_ID);
+    let remote = remote.with_chain_id(CHAIN_ID);
+    let tx: TypedTransaction = TransactionRequest::new()
+        .to(Address::repeat_byte(0x11))
+        .value(U256::from(1_000))
+        .nonce(7)
+        .gas(21_000)
+        .gas_price(1_000_000_000u64)
+        .into();
+
+    let signature = remote.sign_transaction(&tx).await.unwrap();
+    assert_eq!(signature, wallet.sign_transaction(&tx).await.unwrap());
+    assert!([35 + CHAIN_ID * 2, 36 + CHAIN_ID * 2].contains(&signature.v));
+    assert_eq!(
+        signature.recover(tx.sighash(CHAIN_ID)).unwrap(),
+        wallet.address()
+    );
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        *requests,
+        [(
+            format!("/api/v1/eth1/sign/{:?}", wallet.address()),
+            json!({ "data": format!("0x{}", hex::encode(tx.rlp(CHAIN_ID))) }),
+        )]
+    );
+}
+
+#[tokio::test]
+async fn remote_ethereum_messages_match_local_signatures() {
+    let (wallet, remote, requests) = ethereum_signer();
+
+    let signature = remote.sign_message("mantis").await.unwrap();
+    assert_eq!(signature, wallet.sign_message("mantis").await.unwrap());
+    assert!([27, 28].contains(&signature.v));
+
+    let requests = requests.lock().unwrap();
+    let data = format!("0x{}", hex::encode("\x19Ethereum Signed Message:\n6mantis"));
+    assert_eq!(requests[0].1, json!({ "data": data }));
+}
+
+#[tokio::test]
+async fn remote_ethereum_signers_reject_unknown_keys() {
+    let (url, _) = spawn_signer(Keys {
+        solana: None,
+        ethereum: Some(ethereum_wallet_from_hex(ETHEREUM_KEY).unwrap()),
+    });
+    let remote: MantisEthereumSigner =
+        RemoteEthereumSigner::new(url, Address::repeat_byte(0x22)).into();
+
+    let err = remote.sign_message("mantis").await.unwrap_err();
+    assert!(err.to_string().starts_with("Remote signer"), "{}", err);
+}
+
+#[test]
+fn remote_solana_signatures_match_the_keypair() {
+    let (keypair, remote) = solana_signer();
+
+    assert_eq!(remote.pubkey(), keypair.pubkey());
+    let signature = remote.try_sign_message(b"mantis").unwrap();
+    assert_eq!(signature, keypair.sign_message(b"mantis"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_solana_signers_work_on_a_multi_threaded_runtime() {
+    let (keypair, remote) = solana_signer();
+
+    let signature = remote.try_sign_message(b"mantis").unwrap();
+    assert_eq!(signature, keypair.sign_message(b"mantis"));
+}
+
+#[tokio::test]
+async fn remote_solana_signers_fail_on_a_current_thread_runtime() {
+    let (_, remote) = solana_signer();
+
+    let err = remote.try_sign_message(b"mantis").unwrap_err();
+    assert!(err.to_string().contains("multi-threaded"), "{}", err);
+}